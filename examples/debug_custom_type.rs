@@ -4,7 +4,7 @@ use tushare_api::{
     FromTushareValue, FromOptionalTushareValue, TushareError,
     DeriveFromTushareData
 };
-use serde_json::{Value, json};
+use serde_json::Value;
 
 // Simple custom type
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +41,7 @@ impl FromOptionalTushareValue for SimpleDecimal {
 }
 
 // Simple test struct
+#[allow(dead_code)]
 #[derive(Debug, Clone, DeriveFromTushareData)]
 pub struct SimpleStock {
     #[tushare(field = "code")]
@@ -1,4 +1,4 @@
-use tushare_api::{TushareClient, Api, request, TushareEntityList};
+use tushare_api::TushareEntityList;
 use tushare_api::DeriveFromTushareData;
 
 #[derive(Debug, Clone, DeriveFromTushareData)]
@@ -11,20 +11,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 示例 1: 使用默认日志配置
     println!("1. 使用默认日志配置:");
-    let client1 = TushareClient::builder()
+    let _client1 = TushareClient::builder()
         .with_token("your_token_here")
         .build()?;
 
     // 示例 2: 自定义日志级别
     println!("\n2. 自定义日志级别为 Debug:");
-    let client2 = TushareClient::builder()
+    let _client2 = TushareClient::builder()
         .with_token("your_token_here")
         .with_log_level(LogLevel::Debug)
         .build()?;
 
     // 示例 3: 详细的日志配置
     println!("\n3. 详细的日志配置:");
-    let client3 = TushareClient::builder()
+    let _client3 = TushareClient::builder()
         .with_token("your_token_here")
         .with_log_level(LogLevel::Trace)
         .log_requests(true)
@@ -46,14 +46,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         log_performance: true,
     };
     
-    let client4 = TushareClient::builder()
+    let _client4 = TushareClient::builder()
         .with_token("your_token_here")
         .with_log_config(log_config)
         .build()?;
 
     // 示例 5: 关闭日志
     println!("\n5. 关闭日志:");
-    let client5 = TushareClient::builder()
+    let _client5 = TushareClient::builder()
         .with_token("your_token_here")
         .with_log_level(LogLevel::Off)
         .build()?;
@@ -2,6 +2,7 @@ use tushare_api::{TushareClient, Api, request, TushareEntityList, TushareRequest
 use tushare_api::DeriveFromTushareData;
 
 /// 股票基本信息
+#[allow(dead_code)]
 #[derive(Debug, Clone, DeriveFromTushareData)]
 struct Stock {
     /// 股票代码
@@ -21,6 +22,7 @@ struct Stock {
 }
 
 /// 股票日线数据
+#[allow(dead_code)]
 #[derive(Debug, Clone, DeriveFromTushareData)]
 struct StockDaily {
     /// 股票代码
@@ -56,6 +58,7 @@ struct SimpleStock {
 }
 
 /// 基金基本信息
+#[allow(dead_code)]
 #[derive(Debug, Clone, DeriveFromTushareData)]
 struct Fund {
     /// 基金代码
@@ -156,7 +156,9 @@ fn main() {
 }
 
 fn demonstrate_usage() {
+    #[cfg(any(feature = "rust_decimal", feature = "bigdecimal", feature = "chrono", feature = "uuid"))]
     use serde_json::json;
+    #[cfg(any(feature = "rust_decimal", feature = "bigdecimal", feature = "chrono", feature = "uuid"))]
     use tushare_api::traits::FromTushareValue;
     
     println!("Demonstrating third-party type conversions:");
@@ -3,7 +3,9 @@
 // 运行方式：
 // cargo run --example tracing_example --features tracing
 
-use tushare_api::{TushareClient, TushareRequest, Api, LogLevel, LogConfig};
+#![allow(unexpected_cfgs)]
+
+use tushare_api::{TushareClient, TushareRequest, Api, LogLevel};
 use std::collections::HashMap;
 
 #[tokio::main]
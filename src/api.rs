@@ -1,162 +1,320 @@
+use crate::error::TushareError;
+use crate::schema::{self, ApiSchema};
 use serde::{Deserialize, Serialize};
 use serde::de;
+use std::borrow::Cow;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
-/// Tushare API enum types
-#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
-pub enum Api {
-    StockBasic,
-    FundBasic,
-    FundDaily,
-    FundPortfolio,
-    Daily,      
-    DailyBasic,
-    MoneyflowMktDc,
-    Weekly,
-    Monthly,
-    IndexDaily,
-    IndexWeekly,
-    IndexMonthly,
-    TradeCal,
-    Margin,
-    StockCompany,
-    MarginDetail,
-    StkHoldernumber,
-    ThsIndex, 
-    ThsMember,
-    ThsDaily,
-    ThsHot,
-    FinaMainbz,
-    FinaMainbzVip,
-    FinaIndicator,
-    Balancesheet,
-    Income,
-    Cashflow,
-    IndexBasic,
-    IndexDailyBasic,
-    Moneyflow,
-    MoneyflowIndustryThs,
-
-    UsBasic,
-    UsDaily,
-    Custom(String), // other apis specified by name
+/// Data category of a Tushare API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiCategory {
+    Stock,
+    Index,
+    Fund,
+    Futures,
+    Options,
+    Bond,
+    HongKong,
+    Us,
+    Forex,
+    Spot,
+    Macro,
+    News,
 }
 
-const API_STOCK_BASIC: &str = "stock_basic";
-const API_FUND_BASIC: &str = "fund_basic";
-const API_FUND_DAILY: &str = "fund_daily";
-const API_FUND_PORTFOLIO: &str = "fund_portfolio";
-const API_DAILY: &str = "daily";
-const API_DAILY_BASIC: &str = "daily_basic";
-const API_MONEYFLOW_MKT_DC: &str = "moneyflow_mkt_dc";
-const API_WEEKLY: &str = "weekly";
-const API_MONTHLY: &str = "monthly";
-const API_INDEX_DAILY: &str = "index_daily";
-const API_INDEX_WEEKLY: &str = "index_weekly";
-const API_INDEX_MONTHLY: &str = "index_monthly";
-const API_TRADE_CAL: &str = "trade_cal";
-const API_MARGIN: &str = "margin";
-const API_STOCK_COMPANY: &str = "stock_company";
-const API_MARGIN_DETAIL: &str = "margin_detail";
-const API_STK_HOLDERNUMBER: &str = "stk_holdernumber";
-const API_THS_INDEX: &str = "ths_index";
-const API_THS_MEMBER: &str = "ths_member";
-const API_THS_DAILY: &str = "ths_daily";
-const API_THS_HOT: &str = "ths_hot";
-const API_FINA_MAINBZ: &str = "fina_mainbz";
-const API_FINA_MAINBZ_VIP: &str = "fina_mainbz_vip";
-const API_FINA_INDICATOR: &str = "fina_indicator";
-const API_BALANCESHEET: &str = "balancesheet";
-const API_INCOME: &str = "income";
-const API_CASHFLOW: &str = "cashflow";
-const API_INDEX_BASIC: &str = "index_basic";
-const API_INDEX_DAILY_BASIC: &str = "index_daily_basic";
-const API_MONEYFLOW: &str = "moneyflow";
-const API_MONEYFLOW_INDUSTRY_THS: &str = "moneyflow_industry_ths";
-const API_US_BASIC: &str = "us_basic";
-const API_US_DAILY: &str = "us_daily";
+/// Catalog entry of a Tushare API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiInfo {
+    /// Name sent as `api_name`
+    pub name: &'static str,
+    pub category: ApiCategory,
+    /// `doc_id` of the API's page on tushare.pro, where recorded
+    pub doc_id: Option<u32>,
+    /// Points (积分) the API requires, where recorded
+    pub points: Option<u32>,
+}
 
-impl Api {
-    fn from_api_str(value: &str) -> Option<Self> {
-        let v = value.trim();
-        if v.is_empty() {
-            return None;
+impl ApiInfo {
+    /// Documentation page of the API, where recorded
+    pub fn doc_url(&self) -> Option<String> {
+        self.doc_id
+            .map(|doc_id| format!("https://tushare.pro/document/2?doc_id={doc_id}"))
+    }
+}
+
+/// Declares the `Api` enum and its catalog from one table of
+/// `Variant = "api_name", Category, doc_id, points;` entries (`_` when unknown)
+macro_rules! api_catalog {
+    (@opt _) => { None };
+    (@opt $value:literal) => { Some($value) };
+    ($($variant:ident = $name:literal, $category:ident, $doc_id:tt, $points:tt;)*) => {
+        /// Tushare API enum types
+        ///
+        /// APIs compare and hash by name, so `Api::Custom("daily".into())` equals [`Api::Daily`].
+        #[derive(Debug, Clone, Serialize, Eq)]
+        pub enum Api {
+            $($variant,)*
+            Custom(String), // other apis specified by name
         }
 
-        match v {
-            API_STOCK_BASIC => Some(Api::StockBasic),
-            API_FUND_BASIC => Some(Api::FundBasic),
-            API_FUND_DAILY => Some(Api::FundDaily),
-            API_FUND_PORTFOLIO => Some(Api::FundPortfolio),
-            API_DAILY => Some(Api::Daily),
-            API_DAILY_BASIC => Some(Api::DailyBasic),
-            API_MONEYFLOW_MKT_DC => Some(Api::MoneyflowMktDc),
-            API_WEEKLY => Some(Api::Weekly),
-            API_MONTHLY => Some(Api::Monthly),
-            API_INDEX_DAILY => Some(Api::IndexDaily),
-            API_INDEX_WEEKLY => Some(Api::IndexWeekly),
-            API_INDEX_MONTHLY => Some(Api::IndexMonthly),
-            API_TRADE_CAL => Some(Api::TradeCal),
-            API_MARGIN => Some(Api::Margin),
-            API_STOCK_COMPANY => Some(Api::StockCompany),
-            API_MARGIN_DETAIL => Some(Api::MarginDetail),
-            API_STK_HOLDERNUMBER => Some(Api::StkHoldernumber),
-            API_THS_INDEX => Some(Api::ThsIndex),
-            API_THS_MEMBER => Some(Api::ThsMember),
-            API_THS_DAILY => Some(Api::ThsDaily),
-            API_THS_HOT => Some(Api::ThsHot),
-            API_FINA_MAINBZ => Some(Api::FinaMainbz),
-            API_FINA_MAINBZ_VIP => Some(Api::FinaMainbzVip),
-            API_FINA_INDICATOR => Some(Api::FinaIndicator),
-            API_BALANCESHEET => Some(Api::Balancesheet),
-            API_INCOME => Some(Api::Income),
-            API_CASHFLOW => Some(Api::Cashflow),
-            API_INDEX_BASIC => Some(Api::IndexBasic),
-            API_INDEX_DAILY_BASIC => Some(Api::IndexDailyBasic),
-            API_MONEYFLOW => Some(Api::Moneyflow),
-            API_MONEYFLOW_INDUSTRY_THS => Some(Api::MoneyflowIndustryThs),
-            API_US_BASIC => Some(Api::UsBasic),
-            API_US_DAILY => Some(Api::UsDaily),
-            _ => None,
+        const ALL: &[Api] = &[$(Api::$variant,)*];
+
+        impl Api {
+            fn from_api_str(value: &str) -> Option<Self> {
+                match value.trim() {
+                    $($name => Some(Api::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Name sent as `api_name`
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Api::$variant => $name,)*
+                    Api::Custom(name) => name,
+                }
+            }
+
+            /// Catalog entry of the API; `None` for [`Api::Custom`] names outside the catalog
+            pub fn info(&self) -> Option<&'static ApiInfo> {
+                match self.resolved().as_ref() {
+                    $(Api::$variant => Some(&ApiInfo {
+                        name: $name,
+                        category: ApiCategory::$category,
+                        doc_id: api_catalog!(@opt $doc_id),
+                        points: api_catalog!(@opt $points),
+                    }),)*
+                    Api::Custom(_) => None,
+                }
+            }
+        }
+    };
+}
+
+api_catalog! {
+    // Stocks: basics and quotes
+    StockBasic = "stock_basic", Stock, 25, 2000;
+    TradeCal = "trade_cal", Stock, 26, 2000;
+    Namechange = "namechange", Stock, 100, _;
+    HsConst = "hs_const", Stock, 104, _;
+    StockCompany = "stock_company", Stock, 112, _;
+    StkManagers = "stk_managers", Stock, 193, _;
+    StkRewards = "stk_rewards", Stock, 194, _;
+    NewShare = "new_share", Stock, 123, _;
+    BakBasic = "bak_basic", Stock, 262, _;
+    Daily = "daily", Stock, 27, 120;
+    Weekly = "weekly", Stock, 144, _;
+    Monthly = "monthly", Stock, 145, _;
+    AdjFactor = "adj_factor", Stock, 28, 2000;
+    DailyBasic = "daily_basic", Stock, 32, 2000;
+    SuspendD = "suspend_d", Stock, 214, _;
+    StkLimit = "stk_limit", Stock, 183, 2000;
+    BakDaily = "bak_daily", Stock, 255, _;
+    StkMins = "stk_mins", Stock, 370, _;
+    GgtDaily = "ggt_daily", Stock, 196, _;
+    GgtMonthly = "ggt_monthly", Stock, 197, _;
+    // Stocks: financials
+    Income = "income", Stock, 33, 2000;
+    Balancesheet = "balancesheet", Stock, 36, 2000;
+    Cashflow = "cashflow", Stock, 44, 2000;
+    Forecast = "forecast", Stock, 45, 2000;
+    Express = "express", Stock, 46, 2000;
+    Dividend = "dividend", Stock, 103, 2000;
+    FinaIndicator = "fina_indicator", Stock, 79, 2000;
+    FinaAudit = "fina_audit", Stock, 80, 500;
+    FinaMainbz = "fina_mainbz", Stock, 81, 2000;
+    FinaMainbzVip = "fina_mainbz_vip", Stock, 81, 5000;
+    IncomeVip = "income_vip", Stock, 33, 5000;
+    BalancesheetVip = "balancesheet_vip", Stock, 36, 5000;
+    CashflowVip = "cashflow_vip", Stock, 44, 5000;
+    ForecastVip = "forecast_vip", Stock, 45, 5000;
+    ExpressVip = "express_vip", Stock, 46, 5000;
+    FinaIndicatorVip = "fina_indicator_vip", Stock, 79, 5000;
+    DisclosureDate = "disclosure_date", Stock, 162, 500;
+    // Stocks: market reference
+    Margin = "margin", Stock, 58, 2000;
+    MarginDetail = "margin_detail", Stock, 59, 2000;
+    TopList = "top_list", Stock, 106, 2000;
+    TopInst = "top_inst", Stock, 107, 2000;
+    Top10Holders = "top10_holders", Stock, 61, 2000;
+    Top10Floatholders = "top10_floatholders", Stock, 62, 2000;
+    StkHoldernumber = "stk_holdernumber", Stock, 166, 600;
+    StkHoldertrade = "stk_holdertrade", Stock, 175, 2000;
+    PledgeStat = "pledge_stat", Stock, 110, 500;
+    PledgeDetail = "pledge_detail", Stock, 111, 500;
+    Repurchase = "repurchase", Stock, 124, 600;
+    ShareFloat = "share_float", Stock, 160, _;
+    BlockTrade = "block_trade", Stock, 161, 300;
+    LimitListD = "limit_list_d", Stock, 298, _;
+    StkFactor = "stk_factor", Stock, 296, _;
+    StkFactorPro = "stk_factor_pro", Stock, 328, _;
+    CyqPerf = "cyq_perf", Stock, 293, _;
+    CyqChips = "cyq_chips", Stock, 294, _;
+    CcassHold = "ccass_hold", Stock, 295, _;
+    CcassHoldDetail = "ccass_hold_detail", Stock, 274, _;
+    HkHold = "hk_hold", Stock, 188, _;
+    ReportRc = "report_rc", Stock, 292, _;
+    BrokerRecommend = "broker_recommend", Stock, 267, _;
+    StkSurv = "stk_surv", Stock, 275, _;
+    HmList = "hm_list", Stock, 311, _;
+    HmDetail = "hm_detail", Stock, 312, _;
+    // Stocks: money flow and concept boards
+    Moneyflow = "moneyflow", Stock, 170, 2000;
+    MoneyflowHsgt = "moneyflow_hsgt", Stock, 47, _;
+    HsgtTop10 = "hsgt_top10", Stock, 48, _;
+    GgtTop10 = "ggt_top10", Stock, 49, _;
+    MoneyflowMktDc = "moneyflow_mkt_dc", Stock, 345, _;
+    MoneyflowIndThs = "moneyflow_ind_ths", Stock, 343, _;
+    MoneyflowIndustryThs = "moneyflow_industry_ths", Stock, _, _;
+    MoneyflowThs = "moneyflow_ths", Stock, 348, _;
+    MoneyflowDc = "moneyflow_dc", Stock, 349, _;
+    Concept = "concept", Stock, 125, _;
+    ConceptDetail = "concept_detail", Stock, 126, _;
+    ThsIndex = "ths_index", Stock, 259, _;
+    ThsMember = "ths_member", Stock, 261, _;
+    ThsDaily = "ths_daily", Stock, 260, _;
+    ThsHot = "ths_hot", Stock, 320, _;
+    DcHot = "dc_hot", Stock, 321, _;
+    KplList = "kpl_list", Stock, 347, _;
+    // Indices
+    IndexBasic = "index_basic", Index, 94, _;
+    IndexDaily = "index_daily", Index, 95, 2000;
+    IndexWeekly = "index_weekly", Index, 171, _;
+    IndexMonthly = "index_monthly", Index, 172, _;
+    IndexWeight = "index_weight", Index, 96, 2000;
+    IndexDailyBasic = "index_daily_basic", Index, 128, 400;
+    IndexClassify = "index_classify", Index, 181, _;
+    IndexMember = "index_member", Index, _, _;
+    IndexMemberAll = "index_member_all", Index, 335, _;
+    IndexGlobal = "index_global", Index, 211, _;
+    DailyInfo = "daily_info", Index, 215, _;
+    SzDailyInfo = "sz_daily_info", Index, 268, _;
+    SwDaily = "sw_daily", Index, 327, _;
+    CiDaily = "ci_daily", Index, 308, _;
+    // Funds
+    FundBasic = "fund_basic", Fund, 19, 2000;
+    FundCompany = "fund_company", Fund, 118, _;
+    FundManager = "fund_manager", Fund, 208, _;
+    FundShare = "fund_share", Fund, 207, _;
+    FundNav = "fund_nav", Fund, 119, 2000;
+    FundDiv = "fund_div", Fund, 120, _;
+    FundPortfolio = "fund_portfolio", Fund, 121, _;
+    FundDaily = "fund_daily", Fund, 127, 5000;
+    FundAdj = "fund_adj", Fund, 199, _;
+    // Futures
+    FutBasic = "fut_basic", Futures, 135, 2000;
+    FutDaily = "fut_daily", Futures, 138, 2000;
+    FutHolding = "fut_holding", Futures, 139, 2000;
+    FutWsr = "fut_wsr", Futures, 140, 2000;
+    FutSettle = "fut_settle", Futures, 141, 2000;
+    FutMapping = "fut_mapping", Futures, 189, _;
+    FutWeeklyDetail = "fut_weekly_detail", Futures, 216, _;
+    // Options
+    OptBasic = "opt_basic", Options, 158, 5000;
+    OptDaily = "opt_daily", Options, 159, 5000;
+    // Bonds
+    CbBasic = "cb_basic", Bond, 185, 2000;
+    CbIssue = "cb_issue", Bond, 186, 2000;
+    CbDaily = "cb_daily", Bond, 187, 2000;
+    CbShare = "cb_share", Bond, 247, _;
+    CbCall = "cb_call", Bond, 269, _;
+    RepoDaily = "repo_daily", Bond, 256, _;
+    YcCb = "yc_cb", Bond, 201, _;
+    BondBlk = "bond_blk", Bond, 271, _;
+    // Hong Kong and US stocks
+    HkBasic = "hk_basic", HongKong, 191, _;
+    HkTradecal = "hk_tradecal", HongKong, 250, _;
+    HkDaily = "hk_daily", HongKong, 192, _;
+    HkMins = "hk_mins", HongKong, 304, _;
+    UsBasic = "us_basic", Us, 252, _;
+    UsTradecal = "us_tradecal", Us, 253, _;
+    UsDaily = "us_daily", Us, 254, _;
+    UsAdjfactor = "us_adjfactor", Us, _, _;
+    // Forex and spot commodities
+    FxObasic = "fx_obasic", Forex, 178, _;
+    FxDaily = "fx_daily", Forex, 179, _;
+    SgeBasic = "sge_basic", Spot, 284, _;
+    SgeDaily = "sge_daily", Spot, 285, _;
+    // Macro economy
+    Shibor = "shibor", Macro, 149, 120;
+    ShiborQuote = "shibor_quote", Macro, 150, 120;
+    ShiborLpr = "shibor_lpr", Macro, 151, 120;
+    Libor = "libor", Macro, 152, 120;
+    Hibor = "hibor", Macro, 153, 120;
+    WzIndex = "wz_index", Macro, 173, _;
+    GzIndex = "gz_index", Macro, 174, _;
+    CnGdp = "cn_gdp", Macro, 227, 600;
+    CnCpi = "cn_cpi", Macro, 228, 600;
+    CnPpi = "cn_ppi", Macro, 245, 600;
+    CnM = "cn_m", Macro, 242, 600;
+    CnPmi = "cn_pmi", Macro, 325, 600;
+    SfMonth = "sf_month", Macro, 310, _;
+    EcoCal = "eco_cal", Macro, 233, _;
+    // News and announcements
+    News = "news", News, 143, _;
+    MajorNews = "major_news", News, 195, _;
+    CctvNews = "cctv_news", News, 154, _;
+    AnnsD = "anns_d", News, 176, _;
+    IrmQaSh = "irm_qa_sh", News, 366, _;
+    IrmQaSz = "irm_qa_sz", News, 367, _;
+}
+
+impl Api {
+    /// The catalog variant for a [`Api::Custom`] name that is in the catalog, else `self`
+    fn resolved(&self) -> Cow<'_, Api> {
+        match self {
+            Api::Custom(name) => Api::from_api_str(name).map_or(Cow::Borrowed(self), Cow::Owned),
+            _ => Cow::Borrowed(self),
         }
     }
 
+    /// Every API of the catalog, excluding [`Api::Custom`]
+    ///
+    /// The catalog lists commonly used APIs; it is not the full list of Tushare APIs.
+    pub fn all() -> &'static [Api] {
+        ALL
+    }
+
     pub fn name(&self) -> String {
-        match self {
-            Api::StockBasic => API_STOCK_BASIC.to_string(),
-            Api::FundBasic => API_FUND_BASIC.to_string(),
-            Api::FundDaily => API_FUND_DAILY.to_string(),
-            Api::FundPortfolio => API_FUND_PORTFOLIO.to_string(),
-            Api::Daily => API_DAILY.to_string(),
-            Api::DailyBasic => API_DAILY_BASIC.to_string(),
-            Api::MoneyflowMktDc => API_MONEYFLOW_MKT_DC.to_string(),
-            Api::Weekly => API_WEEKLY.to_string(),
-            Api::Monthly => API_MONTHLY.to_string(),
-            Api::IndexDaily => API_INDEX_DAILY.to_string(),
-            Api::IndexWeekly => API_INDEX_WEEKLY.to_string(),
-            Api::IndexMonthly => API_INDEX_MONTHLY.to_string(),
-            Api::TradeCal => API_TRADE_CAL.to_string(),
-            Api::Margin => API_MARGIN.to_string(),
-            Api::StockCompany => API_STOCK_COMPANY.to_string(),
-            Api::MarginDetail => API_MARGIN_DETAIL.to_string(),
-            Api::StkHoldernumber => API_STK_HOLDERNUMBER.to_string(),
-            Api::ThsIndex => API_THS_INDEX.to_string(),
-            Api::ThsMember => API_THS_MEMBER.to_string(),
-            Api::ThsDaily => API_THS_DAILY.to_string(),
-            Api::ThsHot => API_THS_HOT.to_string(),
-            Api::FinaMainbz => API_FINA_MAINBZ.to_string(),
-            Api::FinaMainbzVip => API_FINA_MAINBZ_VIP.to_string(),
-            Api::FinaIndicator => API_FINA_INDICATOR.to_string(),
-            Api::Balancesheet => API_BALANCESHEET.to_string(),
-            Api::Income => API_INCOME.to_string(),
-            Api::Cashflow => API_CASHFLOW.to_string(),
-            Api::IndexBasic => API_INDEX_BASIC.to_string(),
-            Api::IndexDailyBasic => API_INDEX_DAILY_BASIC.to_string(),
-            Api::Moneyflow => API_MONEYFLOW.to_string(),
-            Api::MoneyflowIndustryThs => API_MONEYFLOW_INDUSTRY_THS.to_string(),
-            Api::UsBasic => API_US_BASIC.to_string(),
-            Api::UsDaily => API_US_DAILY.to_string(),
-            Api::Custom(name) => name.clone(),
+        self.as_str().to_string()
+    }
+
+    /// Data category of the API; `None` outside the catalog
+    pub fn category(&self) -> Option<ApiCategory> {
+        self.info().map(|info| info.category)
+    }
+
+    /// Documentation page of the API, where recorded
+    pub fn doc_url(&self) -> Option<String> {
+        self.info().and_then(ApiInfo::doc_url)
+    }
+
+    /// Points (积分) the API requires, where recorded
+    pub fn points(&self) -> Option<u32> {
+        self.info().and_then(|info| info.points)
+    }
+
+    /// Parameters and fields of the API, where recorded. See [`crate::schema`].
+    pub fn schema(&self) -> Option<&'static ApiSchema> {
+        schema::schema_of(&self.resolved())
+    }
+
+    /// Maximum rows one call of this API returns, as documented by Tushare
+    ///
+    /// `None` for APIs without a known cap. Tushare changes limits from time to time; use
+    /// [`TushareClientBuilder::with_row_cap`](crate::client::TushareClientBuilder::with_row_cap) to
+    /// correct or add one.
+    pub fn row_cap(&self) -> Option<usize> {
+        match self.resolved().as_ref() {
+            Api::Daily | Api::DailyBasic | Api::Moneyflow => Some(6000),
+            Api::Weekly | Api::Monthly => Some(4500),
+            Api::IndexDaily => Some(8000),
+            Api::IndexWeekly | Api::IndexMonthly => Some(1000),
+            Api::FundDaily => Some(2000),
+            Api::ThsDaily => Some(3000),
+            _ => None,
         }
     }
 }
@@ -197,6 +355,37 @@ impl<'de> Deserialize<'de> for Api {
     }
 }
 
+impl PartialEq for Api {
+    fn eq(&self, other: &Self) -> bool {
+        self.resolved().as_str() == other.resolved().as_str()
+    }
+}
+
+impl Hash for Api {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.resolved().as_str().hash(state);
+    }
+}
+
+impl fmt::Display for Api {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Api {
+    type Err = TushareError;
+
+    /// Parse an API name; names outside the catalog become [`Api::Custom`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if name.is_empty() {
+            return Err(TushareError::ParseError("Empty API name".to_string()));
+        }
+        Ok(Api::from_api_str(name).unwrap_or_else(|| Api::Custom(name.to_string())))
+    }
+}
+
 /// Serialize Api enum to string
 pub fn serialize_api_name<S>(api: &Api, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -204,3 +393,35 @@ where
 {
     serializer.serialize_str(&api.name())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalog_names_round_trip() {
+        for api in Api::all() {
+            assert_eq!(api.to_string().parse::<Api>().unwrap(), *api);
+            assert_eq!(api.info().unwrap().name, api.as_str());
+        }
+        assert_eq!("daily".parse::<Api>().unwrap(), Api::Daily);
+        assert_eq!(Api::Daily.category(), Some(ApiCategory::Stock));
+        assert_eq!(Api::Daily.doc_url().as_deref(), Some("https://tushare.pro/document/2?doc_id=27"));
+        assert_eq!(Api::AdjFactor.points(), Some(2000));
+        assert_eq!("stk_auction".parse::<Api>().unwrap(), Api::Custom("stk_auction".to_string()));
+        assert!(Api::Custom("stk_auction".to_string()).info().is_none());
+        assert!(" ".parse::<Api>().is_err());
+    }
+
+    #[test]
+    fn custom_catalog_names_match_their_variant() {
+        let custom = Api::Custom("daily".to_string());
+        assert_eq!(custom, Api::Daily);
+        assert_eq!(custom.row_cap(), Api::Daily.row_cap());
+        assert_eq!(custom.info(), Api::Daily.info());
+        assert!(custom.schema().is_some());
+
+        let apis: std::collections::HashSet<Api> = [custom, Api::Daily].into_iter().collect();
+        assert_eq!(apis.len(), 1);
+    }
+}
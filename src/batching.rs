@@ -0,0 +1,433 @@
+//! Transparent batching of single-code requests into multi-code calls
+//!
+//! Many endpoints (`daily`, `daily_basic`, `moneyflow`, ...) accept a comma-separated
+//! `ts_code` list, so one call per code wastes quota. With batching enabled for an API (see
+//! [`TushareClientEx::with_batching`](crate::TushareClientEx::with_batching)), concurrent
+//! requests for a single `ts_code` that share the API, the other params and the fields are
+//! collected for a short window and sent as one request. The rows of the reply are split
+//! back to each caller by `ts_code`.
+//!
+//! Codes are compared in their canonical form (trimmed, upper case), as in
+//! [`RequestKey`], so `000001.sz` gets the rows Tushare returns for `000001.SZ`.
+//!
+//! The first caller of a batch waits for the window (or until the batch holds `max_codes`
+//! codes) and sends the merged request; the others wait for their rows. If the merged call
+//! fails, every caller gets the error as [`TushareError::Shared`]. If the sending caller is
+//! cancelled, the waiting callers send their own requests.
+//!
+//! A merged reply that reaches the API's row cap (see [`crate::truncation`]) may be missing
+//! rows of some codes, so its rows are not handed out: every caller sends its own request
+//! instead. Batch sizes should keep `codes × rows per code` under the cap.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::{Api, TushareClient, TushareClientEx};
+//! use tushare_api::batching::BatchConfig;
+//!
+//! # fn build(inner: TushareClient) -> TushareClientEx {
+//! TushareClientEx::new(inner).with_batching(
+//!     BatchConfig::new()
+//!         .with_api(Api::Daily)
+//!         .with_window(Duration::from_millis(20))
+//!         .with_max_codes(50),
+//! )
+//! # }
+//! ```
+
+use crate::api::Api;
+use crate::error::{ErrorKind, TushareError, TushareResult};
+use crate::logging::Logger;
+use crate::types::{self, RequestKey, TushareData, TushareRequest, TushareResponse};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::{Notify, oneshot};
+
+/// Request parameter and response field holding the security code
+pub const TS_CODE: &str = "ts_code";
+
+/// Which APIs are batched, and how
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// How long the first request of a batch waits for others to join
+    pub window: Duration,
+    /// Maximum number of distinct codes in one merged request
+    pub max_codes: usize,
+    /// APIs whose requests are batched, by name
+    pub apis: HashSet<String>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(10),
+            max_codes: 50,
+            apis: HashSet::new(),
+        }
+    }
+}
+
+impl BatchConfig {
+    /// Default configuration: 10ms window, up to 50 codes, no API batched yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Batch requests of `api`; it must accept a comma-separated `ts_code` list
+    pub fn with_api(mut self, api: Api) -> Self {
+        self.apis.insert(api.name());
+        self
+    }
+
+    /// Set how long a batch collects requests
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the maximum number of codes per merged request
+    pub fn with_max_codes(mut self, max_codes: usize) -> Self {
+        self.max_codes = max_codes.max(1);
+        self
+    }
+}
+
+type Outcome = Result<TushareResponse, Arc<TushareError>>;
+
+struct Waiter {
+    code: String,
+    reply: oneshot::Sender<Outcome>,
+}
+
+/// A batch collecting requests; shared by the pending table and its first caller
+struct Batch {
+    leader_code: String,
+    waiters: Mutex<Vec<Waiter>>,
+    full: Notify,
+}
+
+impl Batch {
+    fn distinct_codes(&self, waiters: &[Waiter]) -> Vec<String> {
+        let mut codes = vec![self.leader_code.clone()];
+        for waiter in waiters {
+            if !codes.contains(&waiter.code) {
+                codes.push(waiter.code.clone());
+            }
+        }
+        codes
+    }
+}
+
+impl std::fmt::Debug for Batch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch").field("leader_code", &self.leader_code).finish_non_exhaustive()
+    }
+}
+
+enum Role {
+    Leader(Arc<Batch>),
+    Follower(oneshot::Receiver<Outcome>),
+}
+
+type Pending = Mutex<HashMap<RequestKey, Arc<Batch>>>;
+
+/// Takes the leader's batch out of the pending table, even if its call is cancelled
+struct PendingGuard<'a> {
+    pending: &'a Pending,
+    key: &'a RequestKey,
+    batch: &'a Arc<Batch>,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let mut pending = lock(self.pending);
+        if pending.get(self.key).is_some_and(|batch| Arc::ptr_eq(batch, self.batch)) {
+            pending.remove(self.key);
+        }
+    }
+}
+
+/// Collects batchable requests of a [`TushareClientEx`](crate::TushareClientEx)
+#[derive(Debug)]
+pub(crate) struct Batcher {
+    config: BatchConfig,
+    pending: Pending,
+}
+
+impl Batcher {
+    pub(crate) fn new(config: BatchConfig) -> Self {
+        Self {
+            config,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The single code of `request` in canonical form, if it can join a batch
+    pub(crate) fn batch_code(&self, request: &TushareRequest) -> Option<String> {
+        if !self.config.apis.contains(&request.api_name.name()) {
+            return None;
+        }
+        let code = types::canonical_code(request.params.get(TS_CODE)?);
+        (!code.is_empty() && !code.contains(',')).then_some(code)
+    }
+
+    /// Send `request` (for the single `code`) as part of a batch; `send` makes the actual call.
+    /// Replies with `row_cap` rows or more are not split, see the module docs.
+    pub(crate) async fn run<F, Fut>(
+        &self,
+        logger: &Logger,
+        request_id: &str,
+        request: TushareRequest,
+        code: String,
+        row_cap: Option<usize>,
+        send: F,
+    ) -> TushareResult<TushareResponse>
+    where
+        F: Fn(TushareRequest) -> Fut,
+        Fut: Future<Output = TushareResult<TushareResponse>>,
+    {
+        let mut template = request.clone();
+        template.params.remove(TS_CODE);
+        let key = template.canonical_key();
+
+        let role = {
+            let mut pending = lock(&self.pending);
+            match pending.get(&key) {
+                Some(batch) => {
+                    let (reply, receiver) = oneshot::channel();
+                    let mut waiters = lock(&batch.waiters);
+                    waiters.push(Waiter { code: code.clone(), reply });
+                    if batch.distinct_codes(&waiters).len() >= self.config.max_codes {
+                        // Seal the batch: later requests start a new one
+                        batch.full.notify_one();
+                        drop(waiters);
+                        pending.remove(&key);
+                    }
+                    Role::Follower(receiver)
+                }
+                None => {
+                    let batch = Arc::new(Batch {
+                        leader_code: code.clone(),
+                        waiters: Mutex::new(Vec::new()),
+                        full: Notify::new(),
+                    });
+                    pending.insert(key.clone(), batch.clone());
+                    Role::Leader(batch)
+                }
+            }
+        };
+
+        let batch = match role {
+            Role::Leader(batch) => batch,
+            Role::Follower(receiver) => {
+                return match receiver.await {
+                    Ok(outcome) => outcome.map_err(TushareError::Shared),
+                    // The first caller was dropped before sending
+                    Err(_) => send(request).await,
+                };
+            }
+        };
+
+        let waiters = {
+            let guard = PendingGuard {
+                pending: &self.pending,
+                key: &key,
+                batch: &batch,
+            };
+            if self.config.max_codes > 1 {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.config.window) => {}
+                    _ = batch.full.notified() => {}
+                }
+            }
+            drop(guard);
+            std::mem::take(&mut *lock(&batch.waiters))
+        };
+        if waiters.is_empty() {
+            return send(request).await;
+        }
+
+        let codes = batch.distinct_codes(&waiters);
+        logger.log_batch_merged(request_id, &request.api_name.name(), codes.len());
+        let mut merged = request.clone();
+        merged.params.insert(TS_CODE.to_string(), codes.join(","));
+        if !merged.fields.is_empty() && !merged.fields.iter().any(|field| field == TS_CODE) {
+            merged.fields.push(TS_CODE.to_string());
+        }
+
+        let at_cap = |response: &TushareResponse| {
+            let rows = response.data.as_ref().map_or(0, |data| data.items.len());
+            row_cap.is_some_and(|row_cap| rows >= row_cap)
+        };
+        match send(merged).await {
+            // Dropping the waiters makes each of them send its own request
+            Ok(response) if at_cap(&response) => {
+                logger.log_batch_at_row_cap(request_id, &request.api_name.name(), codes.len());
+                drop(waiters);
+                send(request).await
+            }
+            Err(err) if err.kind() == ErrorKind::PossiblyTruncated => {
+                logger.log_batch_at_row_cap(request_id, &request.api_name.name(), codes.len());
+                drop(waiters);
+                send(request).await
+            }
+            Ok(response) => {
+                for waiter in waiters {
+                    let rows = split(&response, &waiter.code, &request.fields).map_err(Arc::new);
+                    let _ = waiter.reply.send(rows);
+                }
+                split(&response, &code, &request.fields)
+            }
+            Err(err) => {
+                let err = Arc::new(err);
+                for waiter in waiters {
+                    let _ = waiter.reply.send(Err(err.clone()));
+                }
+                Err(TushareError::Shared(err))
+            }
+        }
+    }
+}
+
+/// The rows of `code` in a merged response, without `ts_code` unless `fields` asked for it
+fn split(response: &TushareResponse, code: &str, fields: &[String]) -> TushareResult<TushareResponse> {
+    let Some(data) = &response.data else {
+        return Ok(response.clone());
+    };
+    let column = data
+        .fields
+        .iter()
+        .position(|field| field == TS_CODE)
+        .ok_or_else(|| TushareError::ParseError("Batched response has no ts_code field".to_string()))?;
+    let keep_code = fields.is_empty() || fields.iter().any(|field| field == TS_CODE);
+
+    let items: Vec<Vec<serde_json::Value>> = data
+        .items
+        .iter()
+        .filter(|row| {
+            row.get(column)
+                .and_then(|value| value.as_str())
+                .is_some_and(|value| types::canonical_code(value) == code)
+        })
+        .map(|row| {
+            let mut row = row.clone();
+            if !keep_code {
+                row.remove(column);
+            }
+            row
+        })
+        .collect();
+    let mut fields = data.fields.clone();
+    if !keep_code {
+        fields.remove(column);
+    }
+
+    Ok(TushareResponse {
+        data: Some(TushareData {
+            fields,
+            count: items.len() as i64,
+            items,
+            has_more: data.has_more,
+        }),
+        ..response.clone()
+    })
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::rows_response;
+    use crate::transport::InMemoryTransport;
+    use crate::{TushareClient, TushareClientEx};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn merges_concurrent_single_code_requests() {
+        let transport = Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let items: Vec<_> = body["params"]["ts_code"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .map(|code| json!([10.5, code]))
+                .collect();
+            Ok(rows_response(&["close", "ts_code"], items))
+        }));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_batching(BatchConfig::new().with_api(Api::Daily).with_max_codes(2));
+        let daily = |code: &str| TushareRequest::new(Api::Daily, [("ts_code", code), ("trade_date", "20240102")], ["close"]);
+        let (a, b, c) = (daily("000001.SZ"), daily("000002.SZ"), daily("000003.SZ"));
+
+        let (a, b, c) = tokio::join!(client.call_api(&a), client.call_api(&b), client.call_api(&c));
+        let (a, b) = (a.unwrap().data.unwrap(), b.unwrap().data.unwrap());
+        assert_eq!(a.fields, vec!["close".to_string()]);
+        assert_eq!((a.items.len(), b.items.len()), (1, 1));
+        assert!(c.is_ok());
+        // The first two codes fill a batch; the third goes alone
+        assert_eq!(transport.requests().len(), 2);
+        assert!(transport.requests()[0].body.contains("000001.SZ,000002.SZ"));
+    }
+
+    /// Echoes one row per requested code, as Tushare spells it
+    fn close_per_code() -> Arc<InMemoryTransport> {
+        Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let items: Vec<_> = body["params"]["ts_code"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .map(|code| json!([10.5, code.trim().to_uppercase()]))
+                .collect();
+            Ok(rows_response(&["close", "ts_code"], items))
+        }))
+    }
+
+    #[tokio::test]
+    async fn matches_codes_in_canonical_form() {
+        let transport = close_per_code();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_batching(BatchConfig::new().with_api(Api::Daily));
+        let daily = |code: &str| TushareRequest::new(Api::Daily, [("ts_code", code)], ["close"]);
+        let (a, b) = (daily("000001.sz"), daily(" 000002.SZ "));
+
+        let (a, b) = tokio::join!(client.call_api(&a), client.call_api(&b));
+        assert_eq!(a.unwrap().data.unwrap().items.len(), 1);
+        assert_eq!(b.unwrap().data.unwrap().items.len(), 1);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn replies_at_the_row_cap_are_fetched_per_code() {
+        let transport = close_per_code();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_row_cap(Api::Daily, 2)
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_batching(BatchConfig::new().with_api(Api::Daily));
+        let daily = |code: &str| TushareRequest::new(Api::Daily, [("ts_code", code)], ["close"]);
+        let (a, b) = (daily("000001.SZ"), daily("000002.SZ"));
+
+        let (a, b) = tokio::join!(client.call_api(&a), client.call_api(&b));
+        assert_eq!(a.unwrap().data.unwrap().items.len(), 1);
+        assert_eq!(b.unwrap().data.unwrap().items.len(), 1);
+        // The merged call, then one call per code
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
@@ -0,0 +1,226 @@
+//! Blocking (synchronous) client, enabled with the `blocking` cargo feature
+//!
+//! [`TushareClient`] offers the same `call_api`/`call_api_as` surface as the async
+//! clients and the same retry and rate-limit behavior as [`TushareClientEx`], but needs
+//! no async runtime at the call site: it drives its own single-threaded tokio runtime.
+//!
+//! Do not call it from within an async context (e.g. inside `#[tokio::main]`);
+//! blocking on a runtime from inside another runtime panics. Use the async clients there.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::blocking::TushareClient;
+//! use tushare_api::client_ex::RetryConfig;
+//! use tushare_api::{Api, TushareEntityList, TushareRequest, request, params, fields};
+//! use tushare_api::DeriveFromTushareData;
+//!
+//! #[derive(Debug, Clone, DeriveFromTushareData)]
+//! struct Stock {
+//!     ts_code: String,
+//!     name: String,
+//! }
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = TushareClient::builder()
+//!         .with_token("your_token_here")
+//!         .with_timeout(Duration::from_secs(60))
+//!         .build_blocking()?
+//!         .with_api_min_interval(Api::Daily, Duration::from_millis(300))
+//!         .with_retry_config(RetryConfig::default());
+//!
+//!     let stocks: TushareEntityList<Stock> =
+//!         client.call_api_as(&request!(Api::StockBasic, { "list_status" => "L" }, ["ts_code", "name"]))?;
+//!     println!("{} stocks", stocks.len());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::api::Api;
+use crate::cache::CacheMode;
+use crate::call_options::CallOptions;
+use crate::client::TushareClientBuilder;
+use crate::client_ex::{RetryConfig, TushareClientEx};
+use crate::circuit_breaker::CircuitBreaker;
+use crate::concurrency::ConcurrencyLimiter;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::error::{TushareError, TushareResult};
+use crate::types::{TushareEntityList, TushareRequest, TushareResponse};
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking Tushare client
+#[derive(Debug)]
+pub struct TushareClient {
+    inner: TushareClientEx,
+    runtime: Runtime,
+}
+
+impl TushareClient {
+    /// Create a client builder; finish it with [`TushareClientBuilder::build_blocking`]
+    pub fn builder() -> TushareClientBuilder {
+        TushareClientBuilder::new()
+    }
+
+    /// Create a blocking client with default settings
+    pub fn new(token: &str) -> TushareResult<Self> {
+        Self::from_async(crate::TushareClient::new(token))
+    }
+
+    /// Create a blocking client from the `TUSHARE_TOKEN` (and `TUSHARE_API_URL`) environment variables
+    pub fn from_env() -> TushareResult<Self> {
+        Self::from_async(crate::TushareClient::from_env()?)
+    }
+
+    /// Create a blocking client with the token read from a file
+    pub fn from_token_file<P: AsRef<std::path::Path>>(path: P) -> TushareResult<Self> {
+        Self::from_async(crate::TushareClient::from_token_file(path)?)
+    }
+
+    /// Wrap an async [`crate::TushareClient`]
+    pub fn from_async(client: crate::TushareClient) -> TushareResult<Self> {
+        Self::from_ex(TushareClientEx::new(client))
+    }
+
+    /// Wrap a configured [`TushareClientEx`], keeping its retry and rate-limit settings
+    pub fn from_ex(inner: TushareClientEx) -> TushareResult<Self> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| TushareError::Other(format!("Failed to create runtime for blocking client: {e}")))?;
+
+        Ok(Self { inner, runtime })
+    }
+
+    /// Configure a minimum interval between two calls of the same API.
+    ///
+    /// See [`TushareClientEx::with_api_min_interval`].
+    pub fn with_api_min_interval(mut self, api: Api, min_interval: Duration) -> Self {
+        self.inner = self.inner.with_api_min_interval(api, min_interval);
+        self
+    }
+
+    /// Enable retry with exponential backoff.
+    ///
+    /// See [`TushareClientEx::with_retry_config`].
+    pub fn with_retry_config(mut self, config: RetryConfig) -> Self {
+        self.inner = self.inner.with_retry_config(config);
+        self
+    }
+
+    /// Enable retry with a custom retry policy.
+    ///
+    /// See [`TushareClientEx::with_retry_policy`].
+    pub fn with_retry_policy<P: RetryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.inner = self.inner.with_retry_policy(policy);
+        self
+    }
+
+    /// Fail fast on APIs that keep failing.
+    ///
+    /// See [`TushareClientEx::with_circuit_breaker`].
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.inner = self.inner.with_circuit_breaker(breaker);
+        self
+    }
+
+    /// Bound the number of in-flight calls.
+    ///
+    /// See [`TushareClientEx::with_concurrency_limiter`].
+    pub fn with_concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.inner = self.inner.with_concurrency_limiter(limiter);
+        self
+    }
+
+    /// Limit calls with a shared token-bucket rate limiter.
+    ///
+    /// See [`TushareClientEx::with_rate_limiter`].
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.inner = self.inner.with_rate_limiter(limiter);
+        self
+    }
+
+    /// Borrow the underlying [`TushareClientEx`].
+    pub fn inner(&self) -> &TushareClientEx {
+        &self.inner
+    }
+
+    /// Call API, blocking the current thread until the response arrives
+    pub fn call_api<T>(&self, request: &T) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.runtime.block_on(self.inner.call_api(request))
+    }
+
+    /// Call API choosing how this call interacts with the response cache
+    pub fn call_api_with_cache_mode<T>(&self, request: &T, cache_mode: CacheMode) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.runtime.block_on(self.inner.call_api_with_cache_mode(request, cache_mode))
+    }
+
+    /// Call API with per-call options (timeout, deadline, cancellation, cache mode)
+    pub fn call_api_with<T>(&self, request: &T, options: CallOptions) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.runtime.block_on(self.inner.call_api_with(request, options))
+    }
+
+    /// Call API and convert the response into a [`TushareEntityList<T>`]
+    pub fn call_api_as<T, R>(&self, request: &R) -> TushareResult<TushareEntityList<T>>
+    where
+        T: crate::traits::FromTushareData,
+        for<'a> &'a R: TryInto<TushareRequest>,
+        for<'a> <&'a R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.runtime.block_on(self.inner.call_api_as(request))
+    }
+}
+
+impl TushareClientBuilder {
+    /// Build a [`blocking::TushareClient`](TushareClient) with the configured options
+    pub fn build_blocking(self) -> TushareResult<TushareClient> {
+        TushareClient::from_async(self.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::rows_transport;
+    use crate::traits::FromTushareData;
+    use crate::{fields, params};
+    use serde_json::json;
+
+    struct Code(String);
+
+    impl FromTushareData for Code {
+        fn from_row(fields: &[String], values: &[serde_json::Value]) -> Result<Self, TushareError> {
+            let value = crate::utils::get_field_value(fields, values, "ts_code")?;
+            Ok(Code(value.as_str().unwrap_or_default().to_string()))
+        }
+    }
+
+    #[test]
+    fn calls_api_without_async_runtime() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(rows_transport(&["ts_code"], vec![json!(["000001.SZ"]), json!(["000002.SZ"])]))
+            .build_blocking()
+            .unwrap()
+            .with_retry_config(RetryConfig::default());
+
+        let request = TushareRequest::new(Api::StockBasic, params!("list_status" => "L"), fields!["ts_code"]);
+        let codes: TushareEntityList<Code> = client.call_api_as(&request).unwrap();
+        assert_eq!(codes.len(), 2);
+        assert_eq!(codes[0].0, "000001.SZ");
+    }
+}
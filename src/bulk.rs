@@ -0,0 +1,434 @@
+//! Resumable bulk download jobs
+//!
+//! A full-history backfill is thousands of requests and takes hours; restarting it from
+//! scratch after a network failure wastes both time and quota. A [`BulkJob`] is a list of
+//! requests ("units") run through a [`TushareClientEx`] with bounded concurrency. Each unit's
+//! response is passed to a handler (e.g. to write it to a database); once the handler
+//! succeeds, the unit is recorded in a local checkpoint file. Downloads keep running while
+//! handlers do, and up to the same number of handlers run at once.
+//!
+//! The checkpoint file is a log with one JSON record per line. Each finished unit appends
+//! one line, so the cost of checkpointing does not grow with the size of the job. The log is
+//! compacted to one line per unit at the start and end of every run.
+//!
+//! Running the job again skips the units recorded as done and runs the others, including
+//! the ones that failed last time. Units are identified by the
+//! [`fingerprint`](crate::types::RequestKey::fingerprint) of their canonical request, so the
+//! list may be rebuilt in any order between runs.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::{Api, TushareClientEx, TushareRequest, TushareResult};
+//! use tushare_api::bulk::BulkJob;
+//!
+//! # async fn example(client: TushareClientEx, codes: Vec<String>) -> TushareResult<()> {
+//! let units = codes.iter().flat_map(|code| {
+//!     [Api::Daily, Api::DailyBasic].map(|api| {
+//!         TushareRequest::new(api, [("ts_code", code.as_str()), ("start_date", "20100101")], Vec::<String>::new())
+//!     })
+//! });
+//! let report = BulkJob::new("backfill.checkpoint.jsonl", units)
+//!     .with_concurrency(4)
+//!     .on_progress(|p| println!("{}/{} done, {} failed", p.done(), p.total, p.failed))
+//!     .run(&client, |request, response| async move {
+//!         // store the response ...
+//!         Ok(())
+//!     })
+//!     .await?;
+//! if !report.is_complete() {
+//!     eprintln!("{} units failed; run the job again to retry them", report.failed.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client_ex::TushareClientEx;
+use crate::error::{TushareError, TushareResult};
+use crate::types::{RequestKey, TushareRequest, TushareResponse};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Progress of a running job, reported after every finished unit
+#[derive(Debug, Clone)]
+pub struct BulkProgress {
+    /// Units in the job
+    pub total: usize,
+    /// Units skipped because an earlier run finished them
+    pub skipped: usize,
+    /// Units finished by this run
+    pub completed: usize,
+    /// Units that failed in this run
+    pub failed: usize,
+    /// The unit that just finished
+    pub unit: RequestKey,
+}
+
+impl BulkProgress {
+    /// Units done so far, including skipped ones
+    pub fn done(&self) -> usize {
+        self.skipped + self.completed
+    }
+
+    /// Units not yet attempted by this run
+    pub fn remaining(&self) -> usize {
+        self.total - self.skipped - self.completed - self.failed
+    }
+}
+
+/// Outcome of one run of a job
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    /// Units in the job
+    pub total: usize,
+    /// Units skipped because an earlier run finished them
+    pub skipped: usize,
+    /// Units finished by this run
+    pub completed: usize,
+    /// Units that failed, with their error
+    pub failed: Vec<(TushareRequest, TushareError)>,
+}
+
+impl BulkReport {
+    /// Whether every unit of the job is done
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// One line of the checkpoint log
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    /// The unit with this fingerprint is done
+    Completed(String),
+    /// The unit with this fingerprint failed
+    Failed { fingerprint: String, error: String },
+}
+
+/// State rebuilt from the checkpoint log
+#[derive(Debug, Default)]
+struct Checkpoint {
+    /// Fingerprints of finished units
+    completed: BTreeSet<String>,
+    /// Last error of failed units, by fingerprint
+    failed: BTreeMap<String, String>,
+}
+
+impl Checkpoint {
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Completed(fingerprint) => {
+                self.failed.remove(&fingerprint);
+                self.completed.insert(fingerprint);
+            }
+            Record::Failed { fingerprint, error } => {
+                if !self.completed.contains(&fingerprint) {
+                    self.failed.insert(fingerprint, error);
+                }
+            }
+        }
+    }
+
+    fn records(&self) -> impl Iterator<Item = Record> + '_ {
+        let completed = self.completed.iter().cloned().map(Record::Completed);
+        let failed = self.failed.iter().map(|(fingerprint, error)| Record::Failed {
+            fingerprint: fingerprint.clone(),
+            error: error.clone(),
+        });
+        completed.chain(failed)
+    }
+}
+
+type ProgressCallback = dyn Fn(&BulkProgress) + Send + Sync;
+
+/// A list of requests downloaded with a checkpoint file
+pub struct BulkJob {
+    checkpoint: PathBuf,
+    units: Vec<TushareRequest>,
+    concurrency: usize,
+    on_progress: Option<Arc<ProgressCallback>>,
+}
+
+impl BulkJob {
+    /// Job over `units`, checkpointed to the file at `checkpoint`; duplicate units run once
+    pub fn new<P, I>(checkpoint: P, units: I) -> Self
+    where
+        P: Into<PathBuf>,
+        I: IntoIterator<Item = TushareRequest>,
+    {
+        let mut seen = HashSet::new();
+        let units = units
+            .into_iter()
+            .filter(|unit| seen.insert(unit.canonical_key()))
+            .collect();
+        Self {
+            checkpoint: checkpoint.into(),
+            units,
+            concurrency: 1,
+            on_progress: None,
+        }
+    }
+
+    /// Run up to `concurrency` units at once (default: 1)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Call `callback` after every finished unit
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&BulkProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Units of the job
+    pub fn units(&self) -> &[TushareRequest] {
+        &self.units
+    }
+
+    /// Run the units not finished yet, passing each response to `handler`.
+    ///
+    /// A unit is checkpointed as done once `handler` returns `Ok`. Handlers may run
+    /// concurrently, up to the job's concurrency. Failed calls and handler
+    /// errors are recorded in the report (and the checkpoint) without stopping the job. Fails
+    /// only if the checkpoint file cannot be read or written.
+    pub async fn run<H, Fut>(&self, client: &TushareClientEx, mut handler: H) -> TushareResult<BulkReport>
+    where
+        H: FnMut(TushareRequest, TushareResponse) -> Fut,
+        Fut: Future<Output = TushareResult<()>>,
+    {
+        let mut checkpoint = load(&self.checkpoint).await?;
+        compact(&self.checkpoint, &checkpoint).await?;
+        let mut log = open_log(&self.checkpoint).await?;
+        let mut report = BulkReport {
+            total: self.units.len(),
+            ..BulkReport::default()
+        };
+
+        let mut pending = Vec::new();
+        for unit in &self.units {
+            let fingerprint = unit.canonical_key().fingerprint();
+            if checkpoint.completed.contains(&fingerprint) {
+                report.skipped += 1;
+            } else {
+                pending.push((fingerprint, unit));
+            }
+        }
+
+        // Handlers run in a second stage, so downloads go on while they are busy
+        let mut results = stream::iter(pending)
+            .map(|(fingerprint, unit)| async move { (fingerprint, unit, client.call_api(unit).await) })
+            .buffer_unordered(self.concurrency)
+            .map(|(fingerprint, unit, result)| {
+                let handled = result.map(|response| handler(unit.clone(), response));
+                async move {
+                    let result = match handled {
+                        Ok(handled) => handled.await,
+                        Err(err) => Err(err),
+                    };
+                    (fingerprint, unit, result)
+                }
+            })
+            .buffer_unordered(self.concurrency);
+        while let Some((fingerprint, unit, result)) = results.next().await {
+            let record = match result {
+                Ok(()) => {
+                    report.completed += 1;
+                    Record::Completed(fingerprint)
+                }
+                Err(err) => {
+                    let record = Record::Failed {
+                        fingerprint,
+                        error: err.to_string(),
+                    };
+                    report.failed.push((unit.clone(), err));
+                    record
+                }
+            };
+            append(&self.checkpoint, &mut log, &record).await?;
+            checkpoint.apply(record);
+
+            if let Some(callback) = &self.on_progress {
+                callback(&BulkProgress {
+                    total: report.total,
+                    skipped: report.skipped,
+                    completed: report.completed,
+                    failed: report.failed.len(),
+                    unit: unit.canonical_key(),
+                });
+            }
+        }
+        drop(results);
+        drop(log);
+        compact(&self.checkpoint, &checkpoint).await?;
+        Ok(report)
+    }
+}
+
+impl fmt::Debug for BulkJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BulkJob")
+            .field("checkpoint", &self.checkpoint)
+            .field("units", &self.units.len())
+            .field("concurrency", &self.concurrency)
+            .finish_non_exhaustive()
+    }
+}
+
+fn io_error(action: &str, path: &Path, e: std::io::Error) -> TushareError {
+    TushareError::Other(format!("Failed to {action} checkpoint {}: {e}", path.display()))
+}
+
+/// Replay the checkpoint log. A torn last line (from a crash mid-write) is ignored.
+async fn load(path: &Path) -> TushareResult<Checkpoint> {
+    let content = match fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Checkpoint::default()),
+        Err(e) => return Err(io_error("read", path, e)),
+    };
+    let mut checkpoint = Checkpoint::default();
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty()).peekable();
+    while let Some(line) = lines.next() {
+        match serde_json::from_str(line) {
+            Ok(record) => checkpoint.apply(record),
+            Err(_) if lines.peek().is_none() => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(checkpoint)
+}
+
+/// Rewrite the log with one record per unit
+async fn compact(path: &Path, checkpoint: &Checkpoint) -> TushareResult<()> {
+    let io_err = |e| io_error("write", path, e);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).await.map_err(io_err)?;
+    }
+    let mut content = String::new();
+    for record in checkpoint.records() {
+        content.push_str(&serde_json::to_string(&record)?);
+        content.push('\n');
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).await.map_err(io_err)?;
+    fs::rename(&tmp, path).await.map_err(io_err)
+}
+
+async fn open_log(path: &Path) -> TushareResult<fs::File> {
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| io_error("open", path, e))
+}
+
+async fn append(path: &Path, log: &mut fs::File, record: &Record) -> TushareResult<()> {
+    let io_err = |e| io_error("write", path, e);
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    log.write_all(line.as_bytes()).await.map_err(io_err)?;
+    log.flush().await.map_err(io_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::{error_response, rows_response, rows_transport};
+    use crate::transport::InMemoryTransport;
+    use crate::{Api, TushareClient};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn resumes_from_the_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("tushare-bulk-{}", crate::client::generate_request_id()));
+        let checkpoint = dir.join("job.jsonl");
+        let failing = Arc::new(AtomicBool::new(true));
+        let transport = Arc::new(InMemoryTransport::new({
+            let failing = failing.clone();
+            move |request| {
+                if failing.load(Ordering::SeqCst) && request.body.contains("000002.SZ") {
+                    return Ok(error_response(40001, "参数错误"));
+                }
+                Ok(rows_response(&["close"], vec![json!([10.5])]))
+            }
+        }));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let units = ["000001.SZ", "000002.SZ", "000003.SZ"]
+            .map(|code| TushareRequest::new(Api::Daily, [("ts_code", code)], ["close"]));
+
+        let job = BulkJob::new(&checkpoint, units).with_concurrency(2);
+        let report = job.run(&client, |_, _| async { Ok(()) }).await.unwrap();
+        assert_eq!((report.completed, report.failed.len()), (2, 1));
+        assert_eq!(std::fs::read_to_string(&checkpoint).unwrap().lines().count(), 3);
+
+        failing.store(false, Ordering::SeqCst);
+        let progress = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let job = job.on_progress({
+            let progress = progress.clone();
+            move |p| progress.lock().unwrap().push(p.done())
+        });
+        let report = job.run(&client, |_, _| async { Ok(()) }).await.unwrap();
+        assert!(report.is_complete());
+        assert_eq!((report.skipped, report.completed), (2, 1));
+        assert_eq!(*progress.lock().unwrap(), vec![3]);
+        assert_eq!(transport.requests().len(), 4);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_continue_while_handlers_run() {
+        let dir = std::env::temp_dir().join(format!("tushare-bulk-{}", crate::client::generate_request_id()));
+        let transport = Arc::new(rows_transport(&["close"], vec![json!([10.5])]));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let units = ["000001.SZ", "000002.SZ", "000003.SZ", "000004.SZ"]
+            .map(|code| TushareRequest::new(Api::Daily, [("ts_code", code)], ["close"]));
+
+        // The first unit's handler holds on until every unit has been requested
+        let report = BulkJob::new(dir.join("job.jsonl"), units)
+            .with_concurrency(2)
+            .run(&client, |request, _| {
+                let transport = transport.clone();
+                async move {
+                    if request.params["ts_code"] != "000001.SZ" {
+                        return Ok(());
+                    }
+                    let all_sent = async {
+                        while transport.requests().len() < 4 {
+                            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                        }
+                    };
+                    tokio::time::timeout(std::time::Duration::from_secs(2), all_sent)
+                        .await
+                        .map_err(|_| TushareError::Other("downloads stalled".to_string()))
+                }
+            })
+            .await
+            .unwrap();
+        assert!(report.is_complete(), "{:?}", report.failed);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+//! Response cache for [`TushareClient`](crate::TushareClient)
+//!
+//! Responses are keyed by the canonical form of the request ([`RequestKey`]): API name,
+//! sorted params and sorted fields. The cache has a bounded in-memory LRU tier and an
+//! optional on-disk tier that survives restarts. Each API can have its own TTL; an API
+//! whose TTL is zero is never cached. Only successful responses (`code == 0`) are stored.
+//! Disk entries found expired on lookup are deleted.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::{Api, TushareClient};
+//! use tushare_api::cache::CacheConfig;
+//!
+//! # fn build() -> tushare_api::TushareResult<TushareClient> {
+//! let client = TushareClient::builder()
+//!     .with_token("your_token_here")
+//!     .with_cache(
+//!         CacheConfig::new(1_000)
+//!             .with_default_ttl(Duration::from_secs(10 * 60))
+//!             .with_api_ttl(Api::StockBasic, Duration::from_secs(24 * 3600))
+//!             .with_api_ttl(Api::TradeCal, Duration::from_secs(24 * 3600))
+//!             .with_disk_dir(".tushare-cache"),
+//!     )
+//!     .build()?;
+//! # Ok(client)
+//! # }
+//! ```
+
+use crate::api::Api;
+use crate::types::{RequestKey, TushareResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+/// How a single call interacts with the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheMode {
+    /// Serve from the cache when possible and store fresh responses (default)
+    #[default]
+    Use,
+    /// Skip the lookup but store the fresh response
+    Refresh,
+    /// Neither read from nor write to the cache
+    Bypass,
+}
+
+impl CacheMode {
+    /// Whether cached responses may be served
+    pub fn reads(self) -> bool {
+        matches!(self, CacheMode::Use)
+    }
+
+    /// Whether fresh responses are stored
+    pub fn writes(self) -> bool {
+        !matches!(self, CacheMode::Bypass)
+    }
+}
+
+/// Cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Maximum number of responses kept in memory
+    pub capacity: usize,
+    /// TTL for APIs without an explicit entry in `api_ttls`
+    pub default_ttl: Duration,
+    /// Per-API TTLs, keyed by API name. A zero TTL disables caching for that API.
+    pub api_ttls: HashMap<String, Duration>,
+    /// Directory of the on-disk tier; `None` keeps the cache in memory only
+    pub disk_dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 512,
+            default_ttl: Duration::from_secs(10 * 60),
+            api_ttls: HashMap::new(),
+            disk_dir: None,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Create a memory-only configuration holding at most `capacity` responses
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Self::default()
+        }
+    }
+
+    /// Set the TTL used for APIs without an explicit TTL
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// Set the TTL of one API; `Duration::ZERO` disables caching for it
+    pub fn with_api_ttl(mut self, api: Api, ttl: Duration) -> Self {
+        self.api_ttls.insert(api.name(), ttl);
+        self
+    }
+
+    /// Enable the on-disk tier in `dir`
+    pub fn with_disk_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.disk_dir = Some(dir.into());
+        self
+    }
+
+    /// TTL applied to responses of `api_name`
+    pub fn ttl_for(&self, api_name: &str) -> Duration {
+        self.api_ttls.get(api_name).copied().unwrap_or(self.default_ttl)
+    }
+}
+
+/// Which tier served a cache hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheTier {
+    Memory,
+    Disk,
+}
+
+impl CacheTier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheTier::Memory => "memory",
+            CacheTier::Disk => "disk",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    request: RequestKey,
+    stored_at_ms: u64,
+    response: TushareResponse,
+}
+
+#[derive(Debug, Default)]
+struct MemoryTier {
+    entries: HashMap<RequestKey, (CacheEntry, u64)>,
+    recency: BTreeMap<u64, RequestKey>,
+    tick: u64,
+}
+
+impl MemoryTier {
+    fn touch(&mut self, key: &RequestKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last_used)) = self.entries.get_mut(key) {
+            self.recency.remove(last_used);
+            *last_used = tick;
+            self.recency.insert(tick, key.clone());
+        }
+    }
+
+    fn get(&mut self, key: &RequestKey) -> Option<CacheEntry> {
+        let entry = self.entries.get(key).map(|(entry, _)| entry.clone())?;
+        self.touch(key);
+        Some(entry)
+    }
+
+    fn insert(&mut self, entry: CacheEntry, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        let key = entry.request.clone();
+        self.remove(&key);
+        while self.entries.len() >= capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (entry, self.tick));
+    }
+
+    fn remove(&mut self, key: &RequestKey) {
+        if let Some((_, last_used)) = self.entries.remove(key) {
+            self.recency.remove(&last_used);
+        }
+    }
+}
+
+/// Two-tier response cache
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    memory: Mutex<MemoryTier>,
+}
+
+impl ResponseCache {
+    /// Create an empty cache
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            memory: Mutex::new(MemoryTier::default()),
+        }
+    }
+
+    /// Cache configuration
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// Whether responses of `api_name` are cached at all
+    pub fn is_cacheable(&self, api_name: &str) -> bool {
+        !self.config.ttl_for(api_name).is_zero()
+    }
+
+    /// Look up a fresh response for `key`, promoting disk hits to memory
+    pub async fn get(&self, key: &RequestKey) -> Option<(TushareResponse, CacheTier)> {
+        let ttl = self.config.ttl_for(&key.api_name);
+        if ttl.is_zero() {
+            return None;
+        }
+        let now = now_ms();
+        let is_fresh = |entry: &CacheEntry| now.saturating_sub(entry.stored_at_ms) < ttl.as_millis() as u64;
+
+        {
+            let mut memory = self.lock_memory();
+            match memory.get(key) {
+                Some(entry) if is_fresh(&entry) => return Some((entry.response, CacheTier::Memory)),
+                Some(_) => memory.remove(key),
+                None => {}
+            }
+        }
+
+        let entry = self.read_disk(key).await?;
+        if !is_fresh(&entry) {
+            self.remove_disk(key).await;
+            return None;
+        }
+        let response = entry.response.clone();
+        self.lock_memory().insert(entry, self.config.capacity);
+        Some((response, CacheTier::Disk))
+    }
+
+    /// Store a successful response
+    pub async fn put(&self, key: &RequestKey, response: &TushareResponse) {
+        if response.code != 0 || !self.is_cacheable(&key.api_name) {
+            return;
+        }
+        let entry = CacheEntry {
+            request: key.clone(),
+            stored_at_ms: now_ms(),
+            response: response.clone(),
+        };
+        self.write_disk(&entry).await;
+        self.lock_memory().insert(entry, self.config.capacity);
+    }
+
+    /// Drop the cached response for `key` from both tiers
+    pub async fn invalidate(&self, key: &RequestKey) {
+        self.lock_memory().remove(key);
+        self.remove_disk(key).await;
+    }
+
+    /// Drop all in-memory entries (the disk tier is left untouched)
+    pub fn clear_memory(&self) {
+        *self.lock_memory() = MemoryTier::default();
+    }
+
+    /// Number of responses held in memory
+    pub fn memory_len(&self) -> usize {
+        self.lock_memory().entries.len()
+    }
+
+    fn lock_memory(&self) -> std::sync::MutexGuard<'_, MemoryTier> {
+        self.memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn disk_path(&self, key: &RequestKey) -> Option<PathBuf> {
+        Some(self.config.disk_dir.as_ref()?.join(key.file_name()))
+    }
+
+    async fn read_disk(&self, key: &RequestKey) -> Option<CacheEntry> {
+        let content = fs::read_to_string(self.disk_path(key)?).await.ok()?;
+        let entry: CacheEntry = serde_json::from_str(&content).ok()?;
+        // Guard against fingerprint collisions
+        (entry.request == *key).then_some(entry)
+    }
+
+    async fn remove_disk(&self, key: &RequestKey) {
+        if let Some(path) = self.disk_path(key) {
+            let _ = fs::remove_file(path).await;
+        }
+    }
+
+    async fn write_disk(&self, entry: &CacheEntry) {
+        let (Some(dir), Some(path)) = (self.config.disk_dir.as_ref(), self.disk_path(&entry.request)) else {
+            return;
+        };
+        let Ok(content) = serde_json::to_string(entry) else {
+            return;
+        };
+        // The disk tier is best effort: a failed write only costs a future cache miss.
+        let tmp = path.with_extension("json.tmp");
+        if fs::create_dir_all(dir).await.is_ok() && fs::write(&tmp, content).await.is_ok() {
+            let _ = fs::rename(&tmp, &path).await;
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{TushareData, TushareRequest};
+    use crate::{fields, params};
+
+    fn response(id: &str) -> TushareResponse {
+        TushareResponse {
+            request_id: id.to_string(),
+            code: 0,
+            msg: None,
+            data: Some(TushareData {
+                fields: vec!["ts_code".to_string()],
+                items: vec![vec![serde_json::json!("000001.SZ")]],
+                has_more: false,
+                count: 1,
+            }),
+        }
+    }
+
+    fn key(code: &str) -> RequestKey {
+        TushareRequest::new(Api::Daily, params!("ts_code" => code), fields!["close"]).canonical_key()
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let cache = ResponseCache::new(CacheConfig::new(2));
+        cache.put(&key("a"), &response("a")).await;
+        cache.put(&key("b"), &response("b")).await;
+        assert!(cache.get(&key("a")).await.is_some());
+        cache.put(&key("c"), &response("c")).await;
+
+        assert!(cache.get(&key("b")).await.is_none());
+        assert!(cache.get(&key("a")).await.is_some());
+        assert!(cache.get(&key("c")).await.is_some());
+        assert_eq!(cache.memory_len(), 2);
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_disables_caching_for_api() {
+        let cache = ResponseCache::new(CacheConfig::new(8).with_api_ttl(Api::Daily, Duration::ZERO));
+        cache.put(&key("a"), &response("a")).await;
+        assert!(cache.get(&key("a")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn disk_tier_survives_new_cache_instance() {
+        let dir = std::env::temp_dir().join(format!("tushare-cache-{}", crate::client::generate_request_id()));
+        let config = CacheConfig::new(8).with_disk_dir(&dir);
+
+        ResponseCache::new(config.clone()).put(&key("a"), &response("a")).await;
+        let (cached, tier) = ResponseCache::new(config).get(&key("a")).await.unwrap();
+        assert_eq!(cached.request_id, "a");
+        assert_eq!(tier, CacheTier::Disk);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_disk_entries_are_deleted() {
+        let dir = std::env::temp_dir().join(format!("tushare-cache-{}", crate::client::generate_request_id()));
+        let config = CacheConfig::new(8)
+            .with_default_ttl(Duration::from_millis(20))
+            .with_disk_dir(&dir);
+
+        ResponseCache::new(config.clone()).put(&key("a"), &response("a")).await;
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(ResponseCache::new(config).get(&key("a")).await.is_none());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
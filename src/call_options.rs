@@ -0,0 +1,156 @@
+//! Per-call options: timeout override, overall deadline and cancellation
+//!
+//! [`CallOptions`] is accepted by `call_api_with` on [`TushareClient`](crate::TushareClient),
+//! [`TushareClientEx`](crate::TushareClientEx) and the blocking client.
+//!
+//! - `timeout` replaces [`HttpClientConfig::timeout`](crate::HttpClientConfig) for each
+//!   HTTP attempt of this call.
+//! - `deadline` bounds the whole call, including `TushareClientEx` rate-limit sleeps and
+//!   retries; when it passes the call fails with [`TushareError::TimeoutError`].
+//! - `cancellation` aborts the call as soon as the token is cancelled, with
+//!   [`TushareError::Cancelled`], even while it is sleeping for a rate limit or a retry.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::{Api, TushareClient, TushareRequest, params, fields};
+//! use tushare_api::call_options::{CallOptions, CancellationToken};
+//!
+//! # async fn example(client: TushareClient) -> tushare_api::TushareResult<()> {
+//! let cancel = CancellationToken::new();
+//! let options = CallOptions::new()
+//!     .with_timeout(Duration::from_secs(120))
+//!     .with_deadline_in(Duration::from_secs(300))
+//!     .with_cancellation(cancel.clone());
+//!
+//! // `cancel.cancel()` from another task aborts the call promptly
+//! let request = TushareRequest::new(Api::StockBasic, params!("list_status" => "L"), fields!["ts_code"]);
+//! let response = client.call_api_with(&request, options).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::CacheMode;
+use crate::error::{TushareError, TushareResult};
+use crate::logging::Logger;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+pub use tokio_util::sync::CancellationToken;
+
+/// Options that apply to a single API call
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// Timeout of each HTTP attempt, replacing the client's configured timeout
+    pub timeout: Option<Duration>,
+    /// Point in time by which the whole call, including retries and rate-limit waits, must finish
+    pub deadline: Option<Instant>,
+    /// Token that aborts the call when cancelled
+    pub cancellation: Option<CancellationToken>,
+    /// How the call interacts with the response cache
+    pub cache_mode: CacheMode,
+}
+
+impl CallOptions {
+    /// Options with no overrides; equivalent to a plain `call_api`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timeout of each HTTP attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the overall deadline
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the overall deadline relative to now
+    pub fn with_deadline_in(self, budget: Duration) -> Self {
+        self.with_deadline(Instant::now() + budget)
+    }
+
+    /// Abort the call when `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Set how the call interacts with the response cache
+    pub fn with_cache_mode(mut self, cache_mode: CacheMode) -> Self {
+        self.cache_mode = cache_mode;
+        self
+    }
+
+    /// Run `call` until it completes, the deadline passes or the call is cancelled
+    pub(crate) async fn run<F, T>(&self, logger: &Logger, request_id: &str, call: F) -> TushareResult<T>
+    where
+        F: Future<Output = TushareResult<T>>,
+    {
+        if self.deadline.is_none() && self.cancellation.is_none() {
+            return call.await;
+        }
+
+        let cancelled = async {
+            match &self.cancellation {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            biased;
+            _ = cancelled => {
+                logger.log_call_aborted(request_id, "cancelled");
+                Err(TushareError::Cancelled)
+            }
+            _ = deadline => {
+                logger.log_call_aborted(request_id, "deadline exceeded");
+                Err(TushareError::TimeoutError)
+            }
+            result = call => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogConfig;
+
+    #[tokio::test]
+    async fn cancellation_and_deadline_abort_a_sleeping_call() {
+        let logger = Logger::new(LogConfig::default());
+        let slow = || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        };
+
+        let token = CancellationToken::new();
+        let options = CallOptions::new().with_cancellation(token.clone());
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            token.cancel();
+        });
+        let started = Instant::now();
+        let result = options.run(&logger, "r1", slow()).await;
+        assert!(matches!(result, Err(TushareError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        canceller.await.unwrap();
+
+        let options = CallOptions::new().with_deadline_in(Duration::from_millis(20));
+        let result = options.run(&logger, "r2", slow()).await;
+        assert!(matches!(result, Err(TushareError::TimeoutError)));
+    }
+}
@@ -0,0 +1,269 @@
+//! Record/replay ("cassette") transport for deterministic offline tests
+//!
+//! In record mode every `(TushareRequest, TushareResponse)` pair that passes through the
+//! client is written to a fixture directory. In replay mode the recorded responses are
+//! served without any network access. Requests are matched on the API name plus the
+//! normalized params and fields (see [`RequestKey`]); the token is never written.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::{TushareClient, TushareEntityList, Api, request, params, fields, TushareRequest};
+//! use tushare_api::cassette::CassetteMode;
+//! use tushare_api::DeriveFromTushareData;
+//!
+//! #[derive(Debug, Clone, DeriveFromTushareData)]
+//! struct Stock {
+//!     ts_code: String,
+//!     name: String,
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // Record once with a real token, then switch to `CassetteMode::Replay` in CI
+//! let client = TushareClient::builder()
+//!     .with_token("your_token_here")
+//!     .with_cassette("tests/fixtures", CassetteMode::ReplayOrRecord)
+//!     .build()?;
+//!
+//! let stocks: TushareEntityList<Stock> = client
+//!     .call_api_as(request!(Api::StockBasic, { "list_status" => "L" }, ["ts_code", "name"]))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{TushareError, TushareResult};
+use crate::transport::{BoxFuture, Transport, TransportRequest, TransportResponse};
+use crate::types::{RequestKey, TushareRequest, TushareResponse};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// How a [`CassetteTransport`] treats requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Always send requests and (over)write their fixtures
+    Record,
+    /// Only serve recorded fixtures; a missing fixture is an error
+    Replay,
+    /// Serve recorded fixtures, sending and recording requests that have none yet
+    ReplayOrRecord,
+}
+
+/// One recorded request/response pair, as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    pub request: RequestKey,
+    pub response: TushareResponse,
+}
+
+/// Transport wrapper that records responses to, or replays them from, a fixture directory
+#[derive(Debug)]
+pub struct CassetteTransport {
+    dir: PathBuf,
+    mode: CassetteMode,
+    inner: Option<Arc<dyn Transport>>,
+}
+
+impl CassetteTransport {
+    /// Wrap `inner`, recording and/or replaying according to `mode`
+    pub fn new<P: Into<PathBuf>>(dir: P, mode: CassetteMode, inner: Arc<dyn Transport>) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+            inner: Some(inner),
+        }
+    }
+
+    /// Replay-only transport that never touches the network
+    pub fn replay<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            mode: CassetteMode::Replay,
+            inner: None,
+        }
+    }
+
+    /// Fixture directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Recording mode
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Path of the fixture file for a request key
+    pub fn fixture_path(&self, key: &RequestKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+
+    fn load(&self, key: &RequestKey) -> TushareResult<Option<CassetteEntry>> {
+        let path = self.fixture_path(key);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(TushareError::Other(format!(
+                    "Failed to read cassette {}: {e}",
+                    path.display()
+                )));
+            }
+        };
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    fn save(&self, entry: &CassetteEntry) -> TushareResult<()> {
+        let path = self.fixture_path(&entry.request);
+        let io_err = |e: std::io::Error| {
+            TushareError::Other(format!("Failed to write cassette {}: {e}", path.display()))
+        };
+
+        fs::create_dir_all(&self.dir).map_err(io_err)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(entry)?).map_err(io_err)?;
+        fs::rename(&tmp, &path).map_err(io_err)
+    }
+
+    async fn record(&self, key: RequestKey, request: TransportRequest) -> TushareResult<TransportResponse> {
+        let inner = self.inner.as_ref().ok_or_else(|| {
+            TushareError::Other("Cassette has no transport to record with".to_string())
+        })?;
+        let response = inner.send(request).await?;
+
+        // Only well-formed Tushare responses are recorded; anything else (e.g. an HTML
+        // error page) is passed through unchanged.
+        if let Ok(parsed) = serde_json::from_str::<TushareResponse>(&response.body) {
+            self.save(&CassetteEntry {
+                request: key,
+                response: parsed,
+            })?;
+        }
+        Ok(response)
+    }
+
+    async fn send_inner(&self, request: TransportRequest) -> TushareResult<TransportResponse> {
+        // The body carries the token as an extra field, which is dropped here.
+        let tushare_request: TushareRequest = serde_json::from_str(&request.body)?;
+        let key = tushare_request.canonical_key();
+
+        if self.mode != CassetteMode::Record {
+            if let Some(entry) = self.load(&key)? {
+                return Ok(TransportResponse::ok(serde_json::to_string(&entry.response)?));
+            }
+            if self.mode == CassetteMode::Replay {
+                return Err(TushareError::Other(format!(
+                    "No cassette recorded for {} at {}",
+                    key.api_name,
+                    self.fixture_path(&key).display()
+                )));
+            }
+        }
+
+        self.record(key, request).await
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn send<'a>(&'a self, request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>> {
+        Box::pin(self.send_inner(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::rows_transport;
+    use crate::{params, fields, Api, TushareClient};
+    use serde_json::json;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "tushare-cassette-{}-{}",
+            name,
+            crate::client::generate_request_id()
+        ))
+    }
+
+    #[tokio::test]
+    async fn records_then_replays_without_network() {
+        let dir = temp_dir("roundtrip");
+        let upstream = Arc::new(rows_transport(&["ts_code", "name"], vec![json!(["000001.SZ", "平安银行"])]));
+
+        let recorder = TushareClient::builder()
+            .with_token("secret-token")
+            .with_transport(CassetteTransport::new(&dir, CassetteMode::Record, upstream.clone()))
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(
+            Api::StockBasic,
+            params!("list_status" => "L", "exchange" => "SZSE"),
+            fields!["ts_code", "name"],
+        );
+        recorder.call_api(&request).await.unwrap();
+
+        let files: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let content = fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+        assert!(!content.contains("secret-token"));
+
+        // Same request with fields in a different order is served from the fixture
+        let replayer = TushareClient::builder()
+            .with_token("another-token")
+            .with_transport(CassetteTransport::replay(&dir))
+            .build()
+            .unwrap();
+        let reordered = TushareRequest::new(
+            Api::StockBasic,
+            params!("exchange" => "SZSE", "list_status" => "L"),
+            fields!["name", "ts_code"],
+        );
+        let response = replayer.call_api(&reordered).await.unwrap();
+        assert_eq!(response.data.unwrap().items[0][0], "000001.SZ");
+        assert_eq!(upstream.requests().len(), 1);
+
+        let missing = TushareRequest::new(Api::Daily, params!("ts_code" => "000001.SZ"), fields!["close"]);
+        assert!(replayer.call_api(&missing).await.is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[derive(Debug, Clone, crate::DeriveFromTushareData)]
+    struct Stock {
+        ts_code: String,
+        #[tushare(field = "name")]
+        stock_name: String,
+        area: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn replays_into_derived_structs() {
+        let dir = temp_dir("derive");
+        let upstream = Arc::new(rows_transport(
+            &["ts_code", "name", "area"],
+            vec![json!(["000001.SZ", "平安银行", "深圳"]), json!(["600000.SH", "浦发银行", null])],
+        ));
+        let request = TushareRequest::new(Api::StockBasic, params!("list_status" => "L"), fields!["ts_code", "name", "area"]);
+        let recorder = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(CassetteTransport::new(&dir, CassetteMode::Record, upstream))
+            .build()
+            .unwrap();
+        recorder.call_api(&request).await.unwrap();
+
+        let replayer = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(CassetteTransport::replay(&dir))
+            .build()
+            .unwrap();
+        let stocks = replayer.call_api_as::<Stock, _>(request).await.unwrap();
+        assert_eq!(stocks.len(), 2);
+        assert_eq!(stocks[0].stock_name, "平安银行");
+        assert_eq!(stocks[0].area.as_deref(), Some("深圳"));
+        assert_eq!((stocks[1].ts_code.as_str(), stocks[1].area.as_deref()), ("600000.SH", None));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,391 @@
+//! Date-range chunking for APIs that cap the rows of one call
+//!
+//! APIs such as `daily`, `moneyflow` or `daily_basic` silently return at most a few thousand
+//! rows per call, so asking for years of data at once loses rows.
+//! [`TushareClientEx::call_api_chunked`](crate::TushareClientEx::call_api_chunked) splits the
+//! request's `start_date`..=`end_date` range into windows, fetches them with bounded
+//! concurrency (every window goes through the client's rate limiting and retries) and merges
+//! the rows in date order.
+//!
+//! Windows span a fixed number of calendar days ([`ChunkConfig::days`]) or of trading days
+//! ([`ChunkConfig::trade_days`], read from the `trade_cal` API). A window that comes back with
+//! as many rows as the row cap (the configured one, else the client's
+//! [`row_cap`](crate::TushareClient::row_cap) for the API) is assumed truncated and is split
+//! in two and fetched again, down to single days. A single day still at the row cap cannot be
+//! split further, so the fetch fails with
+//! [`TushareError::PossiblyTruncated`](crate::TushareError::PossiblyTruncated) whatever the
+//! client's truncation policy; narrow the request (e.g. fewer codes) instead.
+//!
+//! Chunked fetches own their splitting: windows bypass
+//! [`TushareClientEx::with_auto_split`](crate::TushareClientEx::with_auto_split), and a window
+//! rejected under [`TruncationPolicy::Error`](crate::truncation::TruncationPolicy::Error) is
+//! split like any other capped window, so no window is split twice.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::{Api, DeriveFromTushareData, TushareClientEx, TushareRequest, TushareResult};
+//! use tushare_api::chunking::ChunkConfig;
+//!
+//! #[derive(Debug, Clone, DeriveFromTushareData)]
+//! struct Daily {
+//!     ts_code: String,
+//!     trade_date: String,
+//!     close: f64,
+//! }
+//!
+//! # async fn example(client: TushareClientEx) -> TushareResult<()> {
+//! let request = TushareRequest::new(
+//!     Api::Daily,
+//!     [("ts_code", "000001.SZ,600000.SH"), ("start_date", "20140101"), ("end_date", "20231231")],
+//!     ["ts_code", "trade_date", "close"],
+//! );
+//! let config = ChunkConfig::trade_days(1000).with_row_cap(6000).with_concurrency(4);
+//! let rows = client.call_api_chunked::<Daily, _>(&request, &config).await?;
+//! println!("{} rows", rows.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client_ex::TushareClientEx;
+use crate::dates;
+use crate::error::{TushareError, TushareResult};
+use crate::traits::FromTushareData;
+use crate::transport::BoxFuture;
+use crate::types::{TushareData, TushareEntityList, TushareRequest};
+use futures::stream::{self, StreamExt, TryStreamExt};
+
+/// Request parameter holding the first date of the range
+pub const START_DATE_PARAM: &str = "start_date";
+/// Request parameter holding the last date of the range
+pub const END_DATE_PARAM: &str = "end_date";
+
+/// How long one window is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkWindow {
+    /// A fixed number of calendar days
+    Days(u32),
+    /// A fixed number of trading days of the configured exchange
+    TradeDays(u32),
+}
+
+/// How a chunked fetch splits and fetches its date range
+#[derive(Debug, Clone)]
+pub struct ChunkConfig {
+    /// Size of each window
+    pub window: ChunkWindow,
+    /// Exchange whose trading calendar sizes [`ChunkWindow::TradeDays`] windows
+    pub exchange: String,
+    /// Maximum number of windows fetched at once
+    pub concurrency: usize,
+    /// Row cap of the API; a window returning this many rows is split and fetched again.
+    /// Defaults to the client's row cap for the API.
+    pub row_cap: Option<usize>,
+    /// Field the merged rows are ordered by, when present in the response
+    pub date_field: String,
+}
+
+impl ChunkConfig {
+    /// Windows of `days` calendar days
+    pub fn days(days: u32) -> Self {
+        Self::with_window(ChunkWindow::Days(days.max(1)))
+    }
+
+    /// Windows of `days` trading days
+    pub fn trade_days(days: u32) -> Self {
+        Self::with_window(ChunkWindow::TradeDays(days.max(1)))
+    }
+
+    fn with_window(window: ChunkWindow) -> Self {
+        Self {
+            window,
+            exchange: "SSE".to_string(),
+            concurrency: 4,
+            row_cap: None,
+            date_field: "trade_date".to_string(),
+        }
+    }
+
+    /// Set the exchange of the trading calendar (default: `SSE`)
+    pub fn with_exchange<S: Into<String>>(mut self, exchange: S) -> Self {
+        self.exchange = exchange.into();
+        self
+    }
+
+    /// Fetch at most `concurrency` windows at once (default: 4)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Split windows that come back with `row_cap` rows or more
+    pub fn with_row_cap(mut self, row_cap: usize) -> Self {
+        self.row_cap = Some(row_cap.max(1));
+        self
+    }
+
+    /// Order the merged rows by `field` (default: `trade_date`)
+    pub fn with_date_field<S: Into<String>>(mut self, field: S) -> Self {
+        self.date_field = field.into();
+        self
+    }
+}
+
+/// Fetch `request` window by window and merge the rows in date order.
+///
+/// A missing `end_date` means today (Beijing time).
+pub(crate) async fn fetch_chunked<T: FromTushareData>(
+    client: &TushareClientEx,
+    request: TushareRequest,
+    config: &ChunkConfig,
+) -> TushareResult<TushareEntityList<T>> {
+    let start_date = request
+        .params
+        .get(START_DATE_PARAM)
+        .cloned()
+        .ok_or_else(|| TushareError::Other("A chunked fetch needs a start_date parameter".to_string()))?;
+    let end_date = request.params.get(END_DATE_PARAM).cloned().unwrap_or_else(dates::today);
+    let job_id = crate::client::generate_request_id();
+
+    let windows = windows(client, config, &start_date, &end_date).await?;
+    let pages: Vec<Vec<TushareData>> = stream::iter(windows)
+        .map(|(start, end)| fetch_window(client, &job_id, &request, start, end, config))
+        .buffered(config.concurrency)
+        .try_collect()
+        .await?;
+
+    let merged = merge(pages.into_iter().flatten(), Some(&config.date_field));
+    let items = merged
+        .items
+        .iter()
+        .map(|row| T::from_row(&merged.fields, row))
+        .collect::<TushareResult<Vec<T>>>()?;
+    let count = items.len() as i64;
+    Ok(TushareEntityList::new(items, false, count))
+}
+
+/// Split `start_date..=end_date` into consecutive, non-overlapping windows
+async fn windows(
+    client: &TushareClientEx,
+    config: &ChunkConfig,
+    start_date: &str,
+    end_date: &str,
+) -> TushareResult<Vec<(String, String)>> {
+    let (start, end) = (dates::parse_date(start_date)?, dates::parse_date(end_date)?);
+    if start > end {
+        return Ok(Vec::new());
+    }
+
+    // Each window starts on a boundary and ends the day before the next boundary
+    let boundaries: Vec<i64> = match config.window {
+        ChunkWindow::Days(days) => (start..=end).step_by(days as usize).collect(),
+        ChunkWindow::TradeDays(days) => {
+            let trade_days = dates::trade_days(client, &config.exchange, start_date, end_date).await?;
+            let mut boundaries = vec![start];
+            for day in trade_days.iter().step_by(days as usize).skip(1) {
+                boundaries.push(dates::parse_date(day)?);
+            }
+            boundaries
+        }
+    };
+
+    Ok(boundaries
+        .iter()
+        .enumerate()
+        .map(|(i, first)| {
+            let last = boundaries.get(i + 1).map_or(end, |next| next - 1);
+            (dates::format_date(*first), dates::format_date(last))
+        })
+        .collect())
+}
+
+/// Fetch one window, splitting it in two while it comes back at the row cap
+fn fetch_window<'a>(
+    client: &'a TushareClientEx,
+    job_id: &'a str,
+    request: &'a TushareRequest,
+    start: String,
+    end: String,
+    config: &'a ChunkConfig,
+) -> BoxFuture<'a, TushareResult<Vec<TushareData>>> {
+    Box::pin(async move {
+        let mut window = request.clone();
+        window.params.insert(START_DATE_PARAM.to_string(), start.clone());
+        window.params.insert(END_DATE_PARAM.to_string(), end.clone());
+        let (first, last) = (dates::parse_date(&start)?, dates::parse_date(&end)?);
+        let row_cap = config.row_cap.or_else(|| client.inner().row_cap(&request.api_name));
+        // Windows are split here, not by the client's auto-split, so no window is split twice
+        let rows = match client.call_request_without_split(&window).await {
+            Ok(response) => {
+                let data = response
+                    .data
+                    .ok_or_else(|| TushareError::ParseError("Missing data in response".to_string()))?;
+                if row_cap.is_none_or(|row_cap| data.items.len() < row_cap) {
+                    return Ok(vec![data]);
+                }
+                data.items.len()
+            }
+            // Rejected under `TruncationPolicy::Error`: split it like any capped window
+            Err(err) => match err.root() {
+                TushareError::PossiblyTruncated { rows, .. } => *rows,
+                _ => return Err(err),
+            },
+        };
+        if first >= last {
+            let api_name = request.api_name.name();
+            client.inner().logger().log_possibly_truncated(job_id, &api_name, rows);
+            return Err(TushareError::PossiblyTruncated { api_name, rows });
+        }
+
+        client
+            .inner()
+            .logger()
+            .log_window_split(job_id, &request.api_name.name(), &start, &end, rows);
+        let middle = first + (last - first) / 2;
+        let mut pages = fetch_window(client, job_id, request, start, dates::format_date(middle), config).await?;
+        pages.extend(fetch_window(client, job_id, request, dates::format_date(middle + 1), end, config).await?);
+        Ok(pages)
+    })
+}
+
+/// Concatenate pages (mapping columns by name) and stably sort the rows by `date_field`, if given
+pub(crate) fn merge(pages: impl Iterator<Item = TushareData>, date_field: Option<&str>) -> TushareData {
+    let mut merged: Option<TushareData> = None;
+    for page in pages {
+        let Some(merged) = merged.as_mut() else {
+            merged = Some(page);
+            continue;
+        };
+        if page.fields == merged.fields {
+            merged.items.extend(page.items);
+            continue;
+        }
+        let columns: Vec<Option<usize>> = merged
+            .fields
+            .iter()
+            .map(|field| page.fields.iter().position(|f| f == field))
+            .collect();
+        merged.items.extend(page.items.into_iter().map(|row| {
+            columns
+                .iter()
+                .map(|column| column.and_then(|i| row.get(i).cloned()).unwrap_or_default())
+                .collect()
+        }));
+    }
+
+    let mut merged = merged.unwrap_or(TushareData {
+        fields: Vec::new(),
+        items: Vec::new(),
+        has_more: false,
+        count: 0,
+    });
+    if let Some(column) = date_field.and_then(|date_field| merged.fields.iter().position(|field| field == date_field)) {
+        merged
+            .items
+            .sort_by(|a, b| a.get(column).and_then(|v| v.as_str()).cmp(&b.get(column).and_then(|v| v.as_str())));
+    }
+    merged.has_more = false;
+    merged.count = merged.items.len() as i64;
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::rows_response;
+    use crate::transport::InMemoryTransport;
+    use crate::{Api, TushareClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Row(String);
+
+    impl FromTushareData for Row {
+        fn from_row(fields: &[String], values: &[serde_json::Value]) -> TushareResult<Self> {
+            Ok(Row(crate::utils::get_string_field(fields, values, "trade_date")?))
+        }
+    }
+
+    /// One row per calendar day, newest first, capped at 4 rows per reply
+    fn daily_rows() -> Arc<InMemoryTransport> {
+        Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let start = dates::parse_date(body["params"]["start_date"].as_str().unwrap()).unwrap();
+            let end = dates::parse_date(body["params"]["end_date"].as_str().unwrap()).unwrap();
+            let items: Vec<_> = (start..=end)
+                .rev()
+                .take(4)
+                .map(|day| json!([dates::format_date(day)]))
+                .collect();
+            Ok(rows_response(&["trade_date"], items))
+        }))
+    }
+
+    #[tokio::test]
+    async fn splits_capped_windows_and_merges_in_date_order() {
+        let transport = daily_rows();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let request = TushareRequest::new(
+            Api::Daily,
+            [("start_date", "20240101"), ("end_date", "20240110")],
+            ["trade_date"],
+        );
+
+        // 10 days in windows of 5 days; each window hits the cap of 4 and is split again
+        let config = ChunkConfig::days(5).with_row_cap(4);
+        let rows = client.call_api_chunked::<Row, _>(&request, &config).await.unwrap();
+        let expected: Vec<Row> = (1..=10).map(|day| Row(format!("202401{day:02}"))).collect();
+        assert_eq!(rows.items, expected);
+        assert_eq!(transport.requests().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn owns_window_splitting_under_auto_split_and_strict_truncation() {
+        let transport = daily_rows();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_row_cap(Api::Daily, 4)
+            .with_truncation_policy(crate::truncation::TruncationPolicy::Error)
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_auto_split(true);
+        let request = TushareRequest::new(
+            Api::Daily,
+            [("start_date", "20240101"), ("end_date", "20240110")],
+            ["trade_date"],
+        );
+
+        let rows = client.call_api_chunked::<Row, _>(&request, &ChunkConfig::days(5)).await.unwrap();
+        let expected: Vec<Row> = (1..=10).map(|day| Row(format!("202401{day:02}"))).collect();
+        assert_eq!(rows.items, expected);
+        assert_eq!(transport.requests().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn single_days_at_the_row_cap_fail_as_truncated() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(daily_rows())
+            .with_truncation_policy(crate::truncation::TruncationPolicy::Ignore)
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let request = TushareRequest::new(
+            Api::Daily,
+            [("start_date", "20240101"), ("end_date", "20240102")],
+            ["trade_date"],
+        );
+
+        // Every day returns its one row, which is the cap
+        let config = ChunkConfig::days(2).with_row_cap(1);
+        let err = client.call_api_chunked::<Row, _>(&request, &config).await.unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::PossiblyTruncated);
+    }
+}
@@ -0,0 +1,377 @@
+//! Per-API circuit breaker for [`TushareClientEx`](crate::TushareClientEx)
+//!
+//! During an outage, retrying jobs keep hammering Tushare. A [`CircuitBreaker`] tracks the
+//! outcome of calls per API (keyed by [`Api::name`](crate::Api::name)) and:
+//!
+//! - **opens** after `consecutive_failures` failures in a row, or once the failure rate over
+//!   the last `window_size` calls reaches `failure_rate`;
+//! - while open, **fails fast** with [`TushareError::CircuitOpen`] without sending anything;
+//! - after `cooldown`, goes **half-open** and lets a single probe call through: success
+//!   closes the circuit, failure opens it for another cooldown.
+//!
+//! Only failures that point at an outage count: network errors, timeouts, HTTP 5xx and
+//! unreadable responses. Business errors such as missing permissions mean Tushare is up.
+//! Like [`RateLimiter`](crate::rate_limit::RateLimiter), the breaker is a cloneable handle
+//! that can be shared by several clients.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::{TushareClient, TushareClientEx};
+//! use tushare_api::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+//!
+//! # fn build(inner: TushareClient) -> TushareClientEx {
+//! let breaker = CircuitBreaker::new(
+//!     CircuitBreakerConfig::new()
+//!         .with_consecutive_failures(5)
+//!         .with_failure_rate(0.5, 20)
+//!         .with_cooldown(Duration::from_secs(30)),
+//! );
+//! TushareClientEx::new(inner).with_circuit_breaker(breaker)
+//! # }
+//! ```
+
+use crate::error::{ErrorKind, TushareError, TushareResult};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// When a circuit opens and how long it stays open
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Open after this many failures in a row
+    pub consecutive_failures: u32,
+    /// Open once this share of the calls in the window failed (0.0..=1.0)
+    pub failure_rate: Option<f64>,
+    /// Number of recent calls the failure rate is computed over; the rate is only
+    /// checked once the window is full
+    pub window_size: usize,
+    /// Time a circuit stays open before a probe call is allowed
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            consecutive_failures: 5,
+            failure_rate: None,
+            window_size: 20,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Default configuration: open after 5 consecutive failures, 30s cooldown
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open after `failures` failures in a row
+    pub fn with_consecutive_failures(mut self, failures: u32) -> Self {
+        self.consecutive_failures = failures.max(1);
+        self
+    }
+
+    /// Also open once `rate` of the last `window_size` calls failed
+    pub fn with_failure_rate(mut self, rate: f64, window_size: usize) -> Self {
+        self.failure_rate = Some(rate.clamp(0.0, 1.0));
+        self.window_size = window_size.max(1);
+        self
+    }
+
+    /// Set how long an open circuit fails fast before probing
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+}
+
+/// State of one API's circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls fail fast
+    Open,
+    /// A probe call is allowed to test recovery
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        })
+    }
+}
+
+/// A change of an API's circuit state, reported so callers can log it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitTransition {
+    pub from: CircuitState,
+    pub to: CircuitState,
+}
+
+#[derive(Debug)]
+struct ApiCircuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    outcomes: VecDeque<bool>,
+    opened_at: Instant,
+    probe_started: Option<Instant>,
+}
+
+impl ApiCircuit {
+    fn new(now: Instant) -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            outcomes: VecDeque::new(),
+            opened_at: now,
+            probe_started: None,
+        }
+    }
+
+    fn transition(&mut self, to: CircuitState, now: Instant) -> Option<CircuitTransition> {
+        let from = self.state;
+        if from == to {
+            return None;
+        }
+        self.state = to;
+        match to {
+            CircuitState::Open => {
+                self.opened_at = now;
+                self.probe_started = None;
+            }
+            CircuitState::Closed => {
+                self.consecutive_failures = 0;
+                self.outcomes.clear();
+                self.probe_started = None;
+            }
+            CircuitState::HalfOpen => {}
+        }
+        Some(CircuitTransition { from, to })
+    }
+}
+
+/// Shareable per-API circuit breaker; clones share state
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: Arc<CircuitBreakerConfig>,
+    circuits: Arc<Mutex<HashMap<String, ApiCircuit>>>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker with every circuit closed
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            circuits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Breaker configuration
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// Current state of the circuit of `api_name`
+    pub fn state(&self, api_name: &str) -> CircuitState {
+        self.lock_circuits()
+            .get(api_name)
+            .map_or(CircuitState::Closed, |circuit| circuit.state)
+    }
+
+    /// Ask to send a call of `api_name`.
+    ///
+    /// Fails with [`TushareError::CircuitOpen`] while the circuit is open, or while a probe
+    /// call is already in flight on a half-open circuit.
+    pub fn try_acquire(&self, api_name: &str) -> TushareResult<Option<CircuitTransition>> {
+        let now = Instant::now();
+        let cooldown = self.config.cooldown;
+        let mut circuits = self.lock_circuits();
+        let circuit = circuits
+            .entry(api_name.to_string())
+            .or_insert_with(|| ApiCircuit::new(now));
+
+        let open_error = |retry_in: Duration| TushareError::CircuitOpen {
+            api_name: api_name.to_string(),
+            retry_in,
+        };
+
+        match circuit.state {
+            CircuitState::Closed => Ok(None),
+            CircuitState::Open => {
+                let open_for = now.saturating_duration_since(circuit.opened_at);
+                if open_for < cooldown {
+                    return Err(open_error(cooldown - open_for));
+                }
+                circuit.probe_started = Some(now);
+                Ok(circuit.transition(CircuitState::HalfOpen, now))
+            }
+            CircuitState::HalfOpen => match circuit.probe_started {
+                // A probe that never reported back (e.g. a cancelled call) expires after a cooldown
+                Some(started) if now.saturating_duration_since(started) < cooldown => {
+                    Err(open_error(cooldown - now.saturating_duration_since(started)))
+                }
+                _ => {
+                    circuit.probe_started = Some(now);
+                    Ok(None)
+                }
+            },
+        }
+    }
+
+    /// Fail like [`Self::try_acquire`] would, without letting a probe call through.
+    ///
+    /// Lets callers fail fast before waiting for other limits, then take the probe with
+    /// [`Self::try_acquire`] right before sending.
+    pub fn check(&self, api_name: &str) -> TushareResult<()> {
+        let now = Instant::now();
+        let cooldown = self.config.cooldown;
+        let circuits = self.lock_circuits();
+        let Some(circuit) = circuits.get(api_name) else {
+            return Ok(());
+        };
+        let since = match circuit.state {
+            CircuitState::Closed => return Ok(()),
+            CircuitState::Open => circuit.opened_at,
+            CircuitState::HalfOpen => match circuit.probe_started {
+                Some(started) => started,
+                None => return Ok(()),
+            },
+        };
+        let elapsed = now.saturating_duration_since(since);
+        if elapsed < cooldown {
+            return Err(TushareError::CircuitOpen {
+                api_name: api_name.to_string(),
+                retry_in: cooldown - elapsed,
+            });
+        }
+        Ok(())
+    }
+
+    /// Record the outcome of a call of `api_name` that [`Self::try_acquire`] let through
+    pub fn record<T>(&self, api_name: &str, result: &TushareResult<T>) -> Option<CircuitTransition> {
+        let now = Instant::now();
+        let config = &self.config;
+        let mut circuits = self.lock_circuits();
+        let circuit = circuits
+            .entry(api_name.to_string())
+            .or_insert_with(|| ApiCircuit::new(now));
+
+        let failed = match result {
+            Ok(_) => false,
+            Err(err) if is_outage(err) => true,
+            Err(err) if is_unrelated(err) => {
+                // The probe told nothing about Tushare; let the next call probe instead
+                if circuit.state == CircuitState::HalfOpen {
+                    circuit.probe_started = None;
+                }
+                return None;
+            }
+            Err(_) => false,
+        };
+
+        if circuit.state == CircuitState::HalfOpen {
+            let to = if failed { CircuitState::Open } else { CircuitState::Closed };
+            return circuit.transition(to, now);
+        }
+        if circuit.state == CircuitState::Open {
+            return None;
+        }
+
+        circuit.consecutive_failures = if failed { circuit.consecutive_failures + 1 } else { 0 };
+        circuit.outcomes.push_back(failed);
+        while circuit.outcomes.len() > config.window_size {
+            circuit.outcomes.pop_front();
+        }
+
+        let too_many_in_a_row = circuit.consecutive_failures >= config.consecutive_failures;
+        let rate_exceeded = config.failure_rate.is_some_and(|rate| {
+            let failures = circuit.outcomes.iter().filter(|failed| **failed).count();
+            circuit.outcomes.len() >= config.window_size
+                && failures as f64 / circuit.outcomes.len() as f64 >= rate
+        });
+        if too_many_in_a_row || rate_exceeded {
+            return circuit.transition(CircuitState::Open, now);
+        }
+        None
+    }
+
+    fn lock_circuits(&self) -> std::sync::MutexGuard<'_, HashMap<String, ApiCircuit>> {
+        self.circuits.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Errors that suggest Tushare (or the way to it) is down
+fn is_outage(err: &TushareError) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Network | ErrorKind::Timeout | ErrorKind::ServerError | ErrorKind::Serialization
+    )
+}
+
+/// Errors that say nothing about Tushare's health
+fn is_unrelated(err: &TushareError) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Cancelled | ErrorKind::QuotaExhausted | ErrorKind::CircuitOpen
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail() -> TushareResult<()> {
+        Err(TushareError::TimeoutError)
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures_and_recovers() {
+        let breaker = CircuitBreaker::new(
+            CircuitBreakerConfig::new()
+                .with_consecutive_failures(2)
+                .with_cooldown(Duration::ZERO),
+        );
+        assert!(breaker.record("daily", &fail()).is_none());
+        let opened = breaker.record("daily", &fail()).unwrap();
+        assert_eq!(opened.to, CircuitState::Open);
+        assert_eq!(breaker.state("weekly"), CircuitState::Closed);
+
+        // Zero cooldown: the next call is a probe
+        let probing = breaker.try_acquire("daily").unwrap().unwrap();
+        assert_eq!(probing.to, CircuitState::HalfOpen);
+        let closed = breaker.record("daily", &Ok(())).unwrap();
+        assert_eq!(closed.to, CircuitState::Closed);
+    }
+
+    #[test]
+    fn fails_fast_while_open() {
+        let breaker = CircuitBreaker::new(
+            CircuitBreakerConfig::new()
+                .with_consecutive_failures(10)
+                .with_failure_rate(0.5, 4),
+        );
+        for result in [fail(), Ok(()), fail(), Ok(())] {
+            breaker.record("daily", &result);
+        }
+        assert_eq!(breaker.state("daily"), CircuitState::Open);
+        assert!(matches!(breaker.try_acquire("daily"), Err(TushareError::CircuitOpen { .. })));
+
+        // Business errors do not count as failures
+        let denied: TushareResult<()> = Err(TushareError::ApiError {
+            code: 40203,
+            message: "没有权限".to_string(),
+            request_id: "r1".to_string(),
+        });
+        assert!(breaker.record("weekly", &denied).is_none());
+        assert_eq!(breaker.state("weekly"), CircuitState::Closed);
+    }
+}
@@ -1,17 +1,121 @@
 use reqwest::Client;
 use std::time::{Duration, Instant};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::error::{TushareError, TushareResult};
 use crate::types::{TushareRequest, TushareResponse, TushareEntityList};
 use crate::api::{Api, serialize_api_name};
 use crate::logging::{LogConfig, LogLevel, Logger};
+use crate::cache::{CacheConfig, CacheMode, ResponseCache};
+use crate::single_flight::{FlightRole, SingleFlight};
+use crate::truncation::TruncationPolicy;
+use crate::cassette::{CassetteMode, CassetteTransport};
+use crate::pagination;
+use futures::Stream;
+use crate::call_options::CallOptions;
+use crate::middleware::{Middleware, RequestContext};
+use crate::secret::SecretToken;
+use crate::token_pool::{AcquireError, PooledToken, TokenLease, TokenPenalty, TokenPool, TokenSelection};
+use crate::transport::{ReqwestTransport, Transport, TransportRequest, TransportResponse};
 use serde::{Serialize};
 use serde_json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Default Tushare API endpoint
+pub const DEFAULT_BASE_URL: &str = "http://api.tushare.pro";
+
+/// Environment variable holding the API endpoint(s), read next to `TUSHARE_TOKEN`.
+///
+/// Multiple endpoints may be given as a comma separated list; they are tried in order.
+pub const BASE_URL_ENV: &str = "TUSHARE_API_URL";
+
+/// Environment variable holding the path of a file containing the token.
+///
+/// Used when `TUSHARE_TOKEN` is not set, e.g. with a token mounted as a Kubernetes secret.
+pub const TOKEN_FILE_ENV: &str = "TUSHARE_TOKEN_FILE";
+
+/// HTTP/HTTPS/SOCKS proxy settings
+///
+/// Supported URL schemes are `http://`, `https://` and, with the `socks` feature,
+/// `socks5://` / `socks5h://`. Without an explicit proxy, the standard `HTTP_PROXY` /
+/// `HTTPS_PROXY` / `NO_PROXY` environment variables are honored.
+#[derive(Clone)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.corp:3128` or `socks5h://127.0.0.1:1080`
+    pub url: String,
+    /// Basic auth credentials as `(username, password)`
+    pub basic_auth: Option<(String, String)>,
+    /// Comma separated hosts that bypass the proxy, e.g. `localhost,.internal`
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Route all requests through the proxy at `url`
+    pub fn new<S: Into<String>>(url: S) -> Self {
+        Self {
+            url: url.into(),
+            basic_auth: None,
+            no_proxy: None,
+        }
+    }
+
+    /// Authenticate against the proxy with basic auth
+    pub fn with_basic_auth<U: Into<String>, P: Into<String>>(mut self, username: U, password: P) -> Self {
+        self.basic_auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Hosts that bypass the proxy, comma separated
+    pub fn with_no_proxy<S: Into<String>>(mut self, no_proxy: S) -> Self {
+        self.no_proxy = Some(no_proxy.into());
+        self
+    }
+
+    fn build(&self) -> TushareResult<reqwest::Proxy> {
+        let mut proxy = reqwest::Proxy::all(&self.url)?;
+        if let Some((username, password)) = &self.basic_auth {
+            proxy = proxy.basic_auth(username, password);
+        }
+        if let Some(no_proxy) = &self.no_proxy {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(no_proxy));
+        }
+        Ok(proxy)
+    }
+}
+
+impl std::fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the proxy password
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.url)
+            .field("basic_auth", &self.basic_auth.as_ref().map(|(username, _)| (username, "***")))
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+/// TLS implementation used for HTTPS endpoints
+///
+/// The backends are compiled in through the `native-tls` (default) and `rustls-tls` features;
+/// selecting a backend that was not compiled in makes the client build fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TlsBackend {
+    /// Whatever backend `reqwest` picks from the enabled features
+    #[default]
+    Default,
+    /// The platform TLS library (OpenSSL, SChannel, Security.framework)
+    NativeTls,
+    /// rustls
+    Rustls,
+}
+
 /// HTTP client configuration for reqwest::Client
 #[derive(Debug, Clone)]
 pub struct HttpClientConfig {
+    /// Ordered list of API endpoints. The first one is the primary endpoint,
+    /// the others are only used when the previous ones fail at the network level.
+    pub endpoints: Vec<String>,
     /// Connection timeout duration
     pub connect_timeout: Duration,
     /// Request timeout duration
@@ -26,11 +130,26 @@ pub struct HttpClientConfig {
     pub tcp_nodelay: bool,
     /// TCP keep-alive duration
     pub tcp_keepalive: Option<Duration>,
+    /// Explicit proxy; `None` falls back to the proxy environment variables
+    pub proxy: Option<ProxyConfig>,
+    /// TLS implementation
+    pub tls_backend: TlsBackend,
+    /// Additional trusted root certificates in PEM format (each entry may hold a bundle)
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Files with additional trusted root certificates in PEM format, read when the client is built
+    pub root_certificate_files: Vec<PathBuf>,
+    /// Whether to trust the built-in root certificates besides the additional ones
+    pub tls_built_in_root_certs: bool,
+    /// Accept gzip compressed responses (requires the `gzip` feature)
+    pub gzip: bool,
+    /// Accept brotli compressed responses (requires the `brotli` feature)
+    pub brotli: bool,
 }
 
 impl Default for HttpClientConfig {
     fn default() -> Self {
         Self {
+            endpoints: vec![DEFAULT_BASE_URL.to_string()],
             connect_timeout: Duration::from_secs(10),
             timeout: Duration::from_secs(30),
             pool_max_idle_per_host: 20,  // Increased for better performance
@@ -38,6 +157,13 @@ impl Default for HttpClientConfig {
             user_agent: Some("tushare-api-rust/1.0.0".to_string()),
             tcp_nodelay: true,  // Reduce latency
             tcp_keepalive: Some(Duration::from_secs(60)),  // Keep connections alive
+            proxy: None,
+            tls_backend: TlsBackend::Default,
+            root_certificates: Vec::new(),
+            root_certificate_files: Vec::new(),
+            tls_built_in_root_certs: true,
+            gzip: cfg!(feature = "gzip"),
+            brotli: cfg!(feature = "brotli"),
         }
     }
 }
@@ -47,6 +173,36 @@ impl HttpClientConfig {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Set the API endpoint, replacing any previously configured endpoints
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.endpoints = vec![base_url.into()];
+        self
+    }
+
+    /// Set the ordered list of API endpoints used for failover
+    pub fn with_endpoints<I, S>(mut self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.endpoints = endpoints.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Append a fallback endpoint, tried after all previously configured ones
+    pub fn with_fallback_endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.endpoints.push(endpoint.into());
+        self
+    }
+
+    /// Replace the endpoints with the ones from the `TUSHARE_API_URL` environment variable, if set
+    pub fn with_endpoints_from_env(mut self) -> Self {
+        if let Some(endpoints) = endpoints_from_env() {
+            self.endpoints = endpoints;
+        }
+        self
+    }
     
     /// Set connection timeout
     pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
@@ -90,8 +246,50 @@ impl HttpClientConfig {
         self
     }
     
+    /// Route requests through a proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Select the TLS implementation
+    pub fn with_tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.tls_backend = backend;
+        self
+    }
+
+    /// Trust an additional root certificate (or bundle) in PEM format
+    pub fn with_root_certificate_pem<B: Into<Vec<u8>>>(mut self, pem: B) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Trust the root certificates in a PEM file, e.g. a corporate CA bundle
+    pub fn with_root_certificate_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.root_certificate_files.push(path.into());
+        self
+    }
+
+    /// Enable or disable the built-in root certificates
+    pub fn with_tls_built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.tls_built_in_root_certs = enabled;
+        self
+    }
+
+    /// Enable or disable gzip response decompression
+    pub fn with_gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    /// Enable or disable brotli response decompression
+    pub fn with_brotli(mut self, enabled: bool) -> Self {
+        self.brotli = enabled;
+        self
+    }
+
     /// Build reqwest::Client with this configuration
-    pub(crate) fn build_client(&self) -> Result<Client, reqwest::Error> {
+    pub(crate) fn build_client(&self) -> TushareResult<Client> {
         let mut builder = Client::builder()
             .connect_timeout(self.connect_timeout)
             .timeout(self.timeout)
@@ -106,8 +304,113 @@ impl HttpClientConfig {
         if let Some(keepalive) = self.tcp_keepalive {
             builder = builder.tcp_keepalive(keepalive);
         }
-        
-        builder.build()
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.build()?);
+        }
+
+        builder = self.apply_tls(builder)?;
+        builder = self.apply_compression(builder)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn apply_tls(&self, builder: reqwest::ClientBuilder) -> TushareResult<reqwest::ClientBuilder> {
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        {
+            let mut builder = match self.tls_backend {
+                TlsBackend::Default => builder,
+                #[cfg(feature = "native-tls")]
+                TlsBackend::NativeTls => builder.use_native_tls(),
+                #[cfg(feature = "rustls-tls")]
+                TlsBackend::Rustls => builder.use_rustls_tls(),
+                #[allow(unreachable_patterns)]
+                backend => {
+                    return Err(TushareError::Other(format!(
+                        "TLS backend {backend:?} is not enabled; enable the matching cargo feature"
+                    )));
+                }
+            };
+
+            let mut bundles = self.root_certificates.clone();
+            for path in &self.root_certificate_files {
+                let pem = std::fs::read(path).map_err(|e| {
+                    TushareError::Other(format!("Failed to read root certificate {}: {e}", path.display()))
+                })?;
+                bundles.push(pem);
+            }
+            for pem in &bundles {
+                for certificate in reqwest::Certificate::from_pem_bundle(pem)? {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+
+            Ok(builder.tls_built_in_root_certs(self.tls_built_in_root_certs))
+        }
+
+        #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+        {
+            if self.tls_backend != TlsBackend::Default
+                || !self.root_certificates.is_empty()
+                || !self.root_certificate_files.is_empty()
+            {
+                return Err(TushareError::Other(
+                    "TLS options require the `native-tls` or `rustls-tls` feature".to_string(),
+                ));
+            }
+            Ok(builder)
+        }
+    }
+
+    fn apply_compression(&self, builder: reqwest::ClientBuilder) -> TushareResult<reqwest::ClientBuilder> {
+        #[cfg(feature = "gzip")]
+        let builder = builder.gzip(self.gzip);
+        #[cfg(not(feature = "gzip"))]
+        if self.gzip {
+            return Err(TushareError::Other("gzip decompression requires the `gzip` feature".to_string()));
+        }
+
+        #[cfg(feature = "brotli")]
+        let builder = builder.brotli(self.brotli);
+        #[cfg(not(feature = "brotli"))]
+        if self.brotli {
+            return Err(TushareError::Other("brotli decompression requires the `brotli` feature".to_string()));
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Read the endpoint list from the `TUSHARE_API_URL` environment variable
+fn endpoints_from_env() -> Option<Vec<String>> {
+    let value = std::env::var(BASE_URL_ENV).ok()?;
+    let endpoints: Vec<String> = value
+        .split(',')
+        .map(|endpoint| endpoint.trim())
+        .filter(|endpoint| !endpoint.is_empty())
+        .map(|endpoint| endpoint.to_string())
+        .collect();
+
+    if endpoints.is_empty() {
+        None
+    } else {
+        Some(endpoints)
+    }
+}
+
+/// Read the token from the `TUSHARE_TOKEN` environment variable, falling back to the
+/// file named by `TUSHARE_TOKEN_FILE`
+fn token_from_env() -> TushareResult<SecretToken> {
+    if let Ok(value) = std::env::var("TUSHARE_TOKEN") {
+        let token = SecretToken::new(value);
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+
+    match std::env::var(TOKEN_FILE_ENV) {
+        Ok(path) if !path.trim().is_empty() => SecretToken::from_file(path.trim()),
+        _ => Err(TushareError::InvalidToken),
     }
 }
 
@@ -135,30 +438,120 @@ struct InternalTushareRequest<'a> {
 /// Tushare API client
 #[derive(Debug)]
 pub struct TushareClient {
-    token: String,
-    client: Client,
+    tokens: TokenPool,
+    transport: Arc<dyn Transport>,
+    endpoints: Vec<String>,
+    cache: Option<Arc<ResponseCache>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    single_flight: Option<SingleFlight>,
+    row_caps: HashMap<String, usize>,
+    truncation: TruncationPolicy,
+    strict: bool,
+    unvalidated: HashSet<String>,
     logger: Logger,
 }
 
 /// Tushare client builder
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct TushareClientBuilder {
-    token: Option<String>,
+    tokens: Vec<PooledToken>,
+    token_selection: TokenSelection,
+    token_quarantine: Option<Duration>,
     http_config: HttpClientConfig,
     log_config: LogConfig,
+    transport: Option<Arc<dyn Transport>>,
+    cassette: Option<(PathBuf, CassetteMode)>,
+    cache: Option<CacheConfig>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    single_flight: bool,
+    row_caps: HashMap<String, usize>,
+    truncation: TruncationPolicy,
+    strict: bool,
+    unvalidated: HashSet<String>,
 }
 
 impl TushareClientBuilder {
     pub fn new() -> Self {
         Self {
-            token: None,
+            tokens: Vec::new(),
+            token_selection: TokenSelection::default(),
+            token_quarantine: None,
             http_config: HttpClientConfig::default(),
             log_config: LogConfig::default(),
+            transport: None,
+            cassette: None,
+            cache: None,
+            middlewares: Vec::new(),
+            single_flight: false,
+            row_caps: HashMap::new(),
+            truncation: TruncationPolicy::default(),
+            strict: false,
+            unvalidated: HashSet::new(),
         }
     }
 
+    /// Use a single token, replacing any previously configured tokens
     pub fn with_token(mut self, token: &str) -> Self {
-        self.token = Some(token.to_string());
+        self.tokens = vec![PooledToken::new("default", token)];
+        self
+    }
+
+    /// Use a single token read from a file (e.g. a mounted Kubernetes secret), replacing
+    /// any previously configured tokens
+    pub fn with_token_file<P: AsRef<Path>>(mut self, path: P) -> TushareResult<Self> {
+        self.tokens = vec![PooledToken::from_file("default", path)?];
+        Ok(self)
+    }
+
+    /// Add a labeled token to the token pool
+    pub fn with_labeled_token(self, label: &str, token: &str) -> Self {
+        self.with_pooled_token(PooledToken::new(label, token))
+    }
+
+    /// Add a token (with optional per-minute budget) to the token pool. See [`crate::token_pool`].
+    pub fn with_pooled_token(mut self, token: PooledToken) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    /// Set how pooled tokens are picked (default: round-robin)
+    pub fn with_token_selection(mut self, selection: TokenSelection) -> Self {
+        self.token_selection = selection;
+        self
+    }
+
+    /// Set how long a token that failed with an auth or quota error is taken out of rotation,
+    /// and how long one denied an API is left out of calls to it
+    pub fn with_token_quarantine(mut self, quarantine: Duration) -> Self {
+        self.token_quarantine = Some(quarantine);
+        self
+    }
+
+    /// Set the API endpoint, replacing any previously configured endpoints
+    pub fn with_base_url<S: Into<String>>(mut self, base_url: S) -> Self {
+        self.http_config = self.http_config.with_base_url(base_url);
+        self
+    }
+
+    /// Set the ordered list of API endpoints used for failover
+    pub fn with_endpoints<I, S>(mut self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.http_config = self.http_config.with_endpoints(endpoints);
+        self
+    }
+
+    /// Append a fallback endpoint, tried after all previously configured ones
+    pub fn with_fallback_endpoint<S: Into<String>>(mut self, endpoint: S) -> Self {
+        self.http_config = self.http_config.with_fallback_endpoint(endpoint);
+        self
+    }
+
+    /// Use the endpoints from the `TUSHARE_API_URL` environment variable, if set
+    pub fn with_endpoints_from_env(mut self) -> Self {
+        self.http_config = self.http_config.with_endpoints_from_env();
         self
     }
 
@@ -183,6 +576,42 @@ impl TushareClientBuilder {
         self.http_config = self.http_config.with_pool_max_idle_per_host(max_idle);
         self
     }
+
+    /// Route requests through a proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.http_config = self.http_config.with_proxy(proxy);
+        self
+    }
+
+    /// Select the TLS implementation
+    pub fn with_tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.http_config = self.http_config.with_tls_backend(backend);
+        self
+    }
+
+    /// Trust an additional root certificate (or bundle) in PEM format
+    pub fn with_root_certificate_pem<B: Into<Vec<u8>>>(mut self, pem: B) -> Self {
+        self.http_config = self.http_config.with_root_certificate_pem(pem);
+        self
+    }
+
+    /// Trust the root certificates in a PEM file
+    pub fn with_root_certificate_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.http_config = self.http_config.with_root_certificate_file(path);
+        self
+    }
+
+    /// Enable or disable gzip response decompression
+    pub fn with_gzip(mut self, enabled: bool) -> Self {
+        self.http_config = self.http_config.with_gzip(enabled);
+        self
+    }
+
+    /// Enable or disable brotli response decompression
+    pub fn with_brotli(mut self, enabled: bool) -> Self {
+        self.http_config = self.http_config.with_brotli(enabled);
+        self
+    }
     
     /// Set pool idle timeout
     pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
@@ -190,6 +619,74 @@ impl TushareClientBuilder {
         self
     }
 
+    /// Use a custom transport instead of the default `reqwest` based one
+    ///
+    /// The endpoints from the HTTP configuration are still used for failover, but the
+    /// connection settings (timeouts, pool, user agent) only apply to the default transport.
+    pub fn with_transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Record responses to, or replay them from, a fixture directory
+    ///
+    /// The cassette wraps the configured transport (or the default one). See [`crate::cassette`].
+    pub fn with_cassette<P: Into<PathBuf>>(mut self, dir: P, mode: CassetteMode) -> Self {
+        self.cassette = Some((dir.into(), mode));
+        self
+    }
+
+    /// Enable the response cache. See [`crate::cache`].
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Append a middleware; middlewares run in the order they are added. See [`crate::middleware`].
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Let concurrent identical requests share one HTTP call. An error handed to several
+    /// callers is returned as [`TushareError::Shared`]. See [`crate::single_flight`].
+    pub fn with_single_flight(mut self, enabled: bool) -> Self {
+        self.single_flight = enabled;
+        self
+    }
+
+    /// Set the row cap of `api`, overriding [`Api::row_cap`]. See [`crate::truncation`].
+    pub fn with_row_cap(mut self, api: Api, row_cap: usize) -> Self {
+        self.row_caps.insert(api.name(), row_cap);
+        self
+    }
+
+    /// Set what happens when a response reaches its API's row cap (default: log a warning)
+    pub fn with_truncation_policy(mut self, policy: TruncationPolicy) -> Self {
+        self.truncation = policy;
+        self
+    }
+
+    /// Validate every request against its API's schema before sending it, failing with
+    /// [`TushareError::InvalidRequest`] on unknown fields or params, missing required params
+    /// or malformed values. Requests are validated after the middlewares' `before` hooks and
+    /// before the cache lookup. See [`crate::schema`].
+    ///
+    /// Schemas cover `stock_basic`, `trade_cal`, `daily`, `weekly`, `monthly`, `adj_factor`,
+    /// `daily_basic`, `moneyflow`, `index_basic`, `index_daily` and `fund_daily`. Requests to
+    /// any other API (including [`Api::Custom`] names outside the catalog) are sent unvalidated, with a warning logged
+    /// on each call; [`Self::with_unvalidated_api`] silences it for an API.
+    pub fn with_strict_validation(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Let a strict client send requests to `api` without validating them or warning
+    pub fn with_unvalidated_api(mut self, api: Api) -> Self {
+        self.unvalidated.insert(api.name());
+        self
+    }
+
     pub fn with_log_config(mut self, log_config: LogConfig) -> Self {
         self.log_config = log_config;
         self
@@ -226,16 +723,31 @@ impl TushareClientBuilder {
     }
 
     pub fn build(self) -> TushareResult<TushareClient> {
-        let token = self.token.ok_or(TushareError::InvalidToken)?;
-        
-        let client = self.http_config.build_client()
-            .map_err(TushareError::HttpError)?;
-
-        Ok(TushareClient {
-            token,
-            client,
-            logger: Logger::new(self.log_config),
-        })
+        let tokens = TokenPool::new(
+            self.tokens,
+            self.token_selection,
+            self.token_quarantine.unwrap_or(TokenPool::DEFAULT_QUARANTINE),
+        )?;
+        let transport = match (self.transport, self.cassette) {
+            (transport, None) => transport,
+            (Some(transport), Some((dir, mode))) => {
+                Some(Arc::new(CassetteTransport::new(dir, mode, transport)) as Arc<dyn Transport>)
+            }
+            (None, Some((dir, CassetteMode::Replay))) => Some(Arc::new(CassetteTransport::replay(dir)) as Arc<dyn Transport>),
+            (None, Some((dir, mode))) => {
+                let inner = Arc::new(ReqwestTransport::from_config(&self.http_config)?);
+                Some(Arc::new(CassetteTransport::new(dir, mode, inner)) as Arc<dyn Transport>)
+            }
+        };
+        let mut client = TushareClient::from_parts(tokens, self.http_config, self.log_config, transport)?;
+        client.cache = self.cache.map(|config| Arc::new(ResponseCache::new(config)));
+        client.middlewares = self.middlewares;
+        client.single_flight = self.single_flight.then(SingleFlight::default);
+        client.row_caps = self.row_caps;
+        client.truncation = self.truncation;
+        client.strict = self.strict;
+        client.unvalidated = self.unvalidated;
+        Ok(client)
     }
 }
 
@@ -249,7 +761,73 @@ impl TushareClient {
         &self.logger
     }
 
+    fn from_parts(
+        tokens: TokenPool,
+        http_config: HttpClientConfig,
+        log_config: LogConfig,
+        transport: Option<Arc<dyn Transport>>,
+    ) -> TushareResult<Self> {
+        if http_config.endpoints.is_empty() {
+            return Err(TushareError::Other("At least one API endpoint must be configured".to_string()));
+        }
+
+        let transport = match transport {
+            Some(transport) => transport,
+            None => Arc::new(ReqwestTransport::from_config(&http_config)?),
+        };
+
+        Ok(TushareClient {
+            tokens,
+            transport,
+            endpoints: http_config.endpoints,
+            cache: None,
+            middlewares: Vec::new(),
+            single_flight: None,
+            row_caps: HashMap::new(),
+            truncation: TruncationPolicy::default(),
+            strict: false,
+            unvalidated: HashSet::new(),
+            logger: Logger::new(log_config),
+        })
+    }
+
+    /// Token pool the client draws its tokens from
+    pub fn token_pool(&self) -> &TokenPool {
+        &self.tokens
+    }
+
+    /// Validate `request` if strict validation is enabled
+    fn check_request(&self, request_id: &str, request: &TushareRequest) -> TushareResult<()> {
+        let api_name = request.api_name.name();
+        if !self.strict || self.unvalidated.contains(&api_name) {
+            return Ok(());
+        }
+        if request.api_name.schema().is_none() {
+            self.logger.log_unvalidated_request(request_id, &api_name);
+            return Ok(());
+        }
+        request.validate()
+    }
+
+    /// Row cap of `api`: the configured one, else the documented [`Api::row_cap`]
+    pub fn row_cap(&self, api: &Api) -> Option<usize> {
+        self.row_caps.get(&api.name()).copied().or_else(|| api.row_cap())
+    }
+
+    /// Response cache, if enabled
+    pub fn cache(&self) -> Option<&Arc<ResponseCache>> {
+        self.cache.as_ref()
+    }
+
+    /// Transport used to send requests
+    pub fn transport(&self) -> &Arc<dyn Transport> {
+        &self.transport
+    }
 
+    /// API endpoints used by this client, in failover order
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
 
     /// Create a new Tushare client with default timeout settings
     /// 
@@ -273,6 +851,9 @@ impl TushareClient {
     /// # Errors
     /// 
     /// Returns `TushareError::InvalidToken` if TUSHARE_TOKEN environment variable does not exist or is empty
+    /// and no token file is named by `TUSHARE_TOKEN_FILE`
+    ///
+    /// If `TUSHARE_API_URL` is set, its comma separated endpoints replace the default endpoint.
     /// 
     /// # Example
     /// 
@@ -284,16 +865,30 @@ impl TushareClient {
     /// # Ok::<(), tushare_api::TushareError>(())
     /// ```
     pub fn from_env() -> TushareResult<Self> {
-        let token = std::env::var("TUSHARE_TOKEN")
-            .map_err(|_| TushareError::InvalidToken)?
-            .trim()
-            .to_string();
-        
-        if token.is_empty() {
-            return Err(TushareError::InvalidToken);
-        }
-        
-        Ok(Self::new(&token))
+        Self::from_env_with_timeout(Duration::from_secs(10), Duration::from_secs(30))
+    }
+
+    /// Create a new Tushare client with the token read from a file, e.g. a mounted Kubernetes secret
+    ///
+    /// Surrounding whitespace in the file is ignored. `TUSHARE_API_URL` is honored as in [`Self::from_env`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `TushareError::InvalidToken` if the file is empty, or `TushareError::Other` if it cannot be read
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use tushare_api::TushareClient;
+    ///
+    /// let client = TushareClient::from_token_file("/var/run/secrets/tushare/token")?;
+    /// # Ok::<(), tushare_api::TushareError>(())
+    /// ```
+    pub fn from_token_file<P: AsRef<Path>>(path: P) -> TushareResult<Self> {
+        let token = SecretToken::from_file(path)?;
+        let http_config = HttpClientConfig::new().with_endpoints_from_env();
+
+        Self::from_parts(TokenPool::single(token)?, http_config, LogConfig::default(), None)
     }
 
     /// Create a new Tushare client from TUSHARE_TOKEN environment variable with custom timeout settings
@@ -321,16 +916,13 @@ impl TushareClient {
     /// # Ok::<(), tushare_api::TushareError>(())
     /// ```
     pub fn from_env_with_timeout(connect_timeout: Duration, timeout: Duration) -> TushareResult<Self> {
-        let token = std::env::var("TUSHARE_TOKEN")
-            .map_err(|_| TushareError::InvalidToken)?
-            .trim()
-            .to_string();
-        
-        if token.is_empty() {
-            return Err(TushareError::InvalidToken);
-        }
-        
-        Ok(Self::with_timeout(&token, connect_timeout, timeout))
+        let token = token_from_env()?;
+        let http_config = HttpClientConfig::new()
+            .with_connect_timeout(connect_timeout)
+            .with_timeout(timeout)
+            .with_endpoints_from_env();
+
+        Self::from_parts(TokenPool::single(token)?, http_config, LogConfig::default(), None)
     }
 
     /// Create a new Tushare client with custom timeout settings
@@ -358,14 +950,9 @@ impl TushareClient {
             .with_connect_timeout(connect_timeout)
             .with_timeout(timeout);
             
-        let client = http_config.build_client()
-            .expect("Failed to create HTTP client");
-
-        TushareClient {
-            token: token.to_string(),
-            client,
-            logger: Logger::new(LogConfig::default()),
-        }
+        TokenPool::single(token)
+            .and_then(|tokens| Self::from_parts(tokens, http_config, LogConfig::default(), None))
+            .expect("Failed to create HTTP client")
     }
 
     /// Call Tushare API with flexible string types support
@@ -407,46 +994,268 @@ impl TushareClient {
             .try_into()
             .map_err(Into::into)?;
         let request_id = generate_request_id();
-        self.call_api_inner_with_request_id(&request_id, &request).await
+        self.call_api_inner_with_request_id(&request_id, &request, &CallOptions::default()).await
     }
 
-    pub(crate) async fn call_api_request(&self, request: &TushareRequest) -> TushareResult<TushareResponse> {
+    /// Call Tushare API, choosing how this call interacts with the response cache
+    ///
+    /// Use [`CacheMode::Bypass`] to always hit the network without touching the cache, or
+    /// [`CacheMode::Refresh`] to skip the lookup but store the fresh response.
+    /// Without a configured cache this behaves exactly like [`Self::call_api`].
+    pub async fn call_api_with_cache_mode<T>(&self, request: &T, cache_mode: CacheMode) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.call_api_with(request, CallOptions::new().with_cache_mode(cache_mode)).await
+    }
+
+    /// Call Tushare API with per-call options: timeout override, deadline, cancellation and cache mode
+    ///
+    /// See [`CallOptions`] for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use tushare_api::{TushareClient, TushareRequest, CallOptions, Api, request, params, fields};
+    ///
+    /// # async fn example(client: TushareClient) -> Result<(), Box<dyn std::error::Error>> {
+    /// // Full stock list takes longer than the default 30s timeout
+    /// let options = CallOptions::new().with_timeout(Duration::from_secs(120));
+    /// let response = client.call_api_with(&request!(Api::StockBasic, {}, ["ts_code"]), options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_api_with<T>(&self, request: &T, options: CallOptions) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        let request = request
+            .try_into()
+            .map_err(Into::into)?;
         let request_id = generate_request_id();
-        self.call_api_inner_with_request_id(&request_id, request).await
+        options
+            .run(&self.logger, &request_id, self.call_api_inner_with_request_id(&request_id, &request, &options))
+            .await
     }
 
     pub(crate) async fn call_api_request_with_request_id(
         &self,
         request_id: &str,
         request: &TushareRequest,
+        options: &CallOptions,
     ) -> TushareResult<TushareResponse> {
-        self.call_api_inner_with_request_id(request_id, request).await
+        self.call_api_inner_with_request_id(request_id, request, options).await
+    }
+
+    /// Look up a cached response, logging the hit or miss
+    async fn cached_response(
+        &self,
+        request_id: &str,
+        request: &TushareRequest,
+        cache_mode: CacheMode,
+    ) -> Option<TushareResponse> {
+        let cache = self.cache.as_ref()?;
+        let api_name = request.api_name.name();
+        if !cache_mode.reads() || !cache.is_cacheable(&api_name) {
+            return None;
+        }
+
+        match cache.get(&request.canonical_key()).await {
+            Some((response, tier)) => {
+                self.logger.log_cache_hit(request_id, &api_name, tier.as_str());
+                Some(response)
+            }
+            None => {
+                self.logger.log_cache_miss(request_id, &api_name);
+                None
+            }
+        }
     }
 
     async fn call_api_inner_with_request_id(
         &self,
         request_id: &str,
         request: &TushareRequest,
+        options: &CallOptions,
+    ) -> TushareResult<TushareResponse> {
+        let cache_mode = options.cache_mode;
+        let mut context = RequestContext::new(request_id);
+        context.timeout = options.timeout;
+        if self.middlewares.is_empty() {
+            return self.call_cached_or_send(&context, request, cache_mode).await;
+        }
+
+        let mut request = request.clone();
+        let mut short_circuit = None;
+        let mut entered = 0;
+        for middleware in &self.middlewares {
+            entered += 1;
+            match middleware.before(&mut context, &mut request).await {
+                Ok(None) => continue,
+                Ok(Some(response)) => {
+                    self.logger.log_middleware_short_circuit(request_id, middleware.name(), false);
+                    short_circuit = Some(Ok(response));
+                }
+                Err(e) => {
+                    self.logger.log_middleware_short_circuit(request_id, middleware.name(), true);
+                    short_circuit = Some(Err(e));
+                }
+            }
+            break;
+        }
+
+        let mut result = match short_circuit {
+            Some(result) => result,
+            None => self.call_cached_or_send(&context, &request, cache_mode).await,
+        };
+        for middleware in self.middlewares[..entered].iter().rev() {
+            middleware.after(&context, &request, &mut result).await;
+        }
+        result
+    }
+
+    async fn call_cached_or_send(
+        &self,
+        context: &RequestContext,
+        request: &TushareRequest,
+        cache_mode: CacheMode,
+    ) -> TushareResult<TushareResponse> {
+        // After the middlewares, so requests they fix up are validated as sent
+        let request_id = context.request_id.as_str();
+        self.check_request(request_id, request)?;
+        if let Some(response) = self.cached_response(request_id, request, cache_mode).await {
+            return Ok(response);
+        }
+
+        // Checked inside the flight, so followers share the leader's verdict
+        let fetch = || async {
+            let response = self.send_request(context, request).await?;
+            self.check_truncation(request_id, request, &response)?;
+            Ok(response)
+        };
+        let response = match &self.single_flight {
+            Some(single_flight) => {
+                let (result, role) = single_flight.run(request.canonical_key(), fetch).await;
+                if role == FlightRole::Follower {
+                    self.logger.log_single_flight_join(request_id, &request.api_name.name());
+                    return result;
+                }
+                result?
+            }
+            None => fetch().await?,
+        };
+
+        if let Some(cache) = &self.cache
+            && cache_mode.writes()
+        {
+            cache.put(&request.canonical_key(), &response).await;
+        }
+
+        Ok(response)
+    }
+
+    /// Apply the truncation policy to a fresh response
+    fn check_truncation(&self, request_id: &str, request: &TushareRequest, response: &TushareResponse) -> TushareResult<()> {
+        if self.truncation == TruncationPolicy::Ignore || pagination::is_paged(request) {
+            return Ok(());
+        }
+        let (Some(row_cap), Some(data)) = (self.row_cap(&request.api_name), &response.data) else {
+            return Ok(());
+        };
+        let rows = data.items.len();
+        if rows < row_cap {
+            return Ok(());
+        }
+
+        let api_name = request.api_name.name();
+        self.logger.log_possibly_truncated(request_id, &api_name, rows);
+        match self.truncation {
+            TruncationPolicy::Error => Err(TushareError::PossiblyTruncated { api_name, rows }),
+            _ => Ok(()),
+        }
+    }
+
+    async fn send_request(
+        &self,
+        context: &RequestContext,
+        request: &TushareRequest,
     ) -> TushareResult<TushareResponse> {
+        let request_id = context.request_id.as_str();
+        let api_name = request.api_name.name();
+        let lease = self.acquire_token(request_id, &api_name).await?;
+        let result = self.send_request_with_token(context, request, &lease).await;
+
+        if let Err(err) = &result
+            && let Some(penalty) = self.tokens.report_error(&lease, &api_name, err)
+        {
+            let error = err.to_string();
+            match penalty {
+                TokenPenalty::Quarantined(duration) => {
+                    self.logger.log_token_quarantined(request_id, &lease.label, duration, &error)
+                }
+                TokenPenalty::Denied(duration) => {
+                    self.logger.log_token_denied(request_id, &lease.label, &api_name, duration, &error)
+                }
+                TokenPenalty::Throttled(duration) => {
+                    self.logger.log_token_throttled(request_id, &lease.label, duration, &error)
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Check out a token from the pool, waiting while every usable token is over budget
+    async fn acquire_token(&self, request_id: &str, api_name: &str) -> TushareResult<TokenLease> {
+        loop {
+            match self.tokens.acquire(api_name) {
+                Ok(lease) => {
+                    self.logger.log_token_selected(request_id, &lease.label);
+                    return Ok(lease);
+                }
+                Err(AcquireError::Wait(wait)) => {
+                    self.logger.log_token_budget_wait(request_id, wait);
+                    tokio::time::sleep(wait).await;
+                }
+                Err(AcquireError::AllQuarantined(remaining)) => {
+                    return Err(TushareError::Other(format!(
+                        "All tokens are quarantined or denied API {api_name}; the first one is released in {remaining:?}"
+                    )));
+                }
+            }
+        }
+    }
+
+    async fn send_request_with_token(
+        &self,
+        context: &RequestContext,
+        request: &TushareRequest,
+        lease: &TokenLease,
+    ) -> TushareResult<TushareResponse> {
+        let request_id = context.request_id.as_str();
         let start_time = Instant::now();
+        let api_name = request.api_name.name();
         // Log API call start
         self.logger.log_api_start(
-            &request_id,
-            &request.api_name.name(),
+            request_id,
+            &api_name,
             request.params.len(),
             request.fields.len()
         );
         
         // Log detailed request information (if enabled)
         let token_preview_string = if self.logger.config().log_sensitive_data {
-            Some(format!("token: {}***", &self.token[..self.token.len().min(8)]))
+            Some(format!("token[{}]: {}", lease.label, lease.token))
         } else {
             None
         };
         
         self.logger.log_request_details(
-            &request_id,
-            &request.api_name.name(),
+            request_id,
+            &api_name,
             &format!("{:?}", request.params),
             &format!("{:?}", request.fields),
             token_preview_string.as_deref()
@@ -454,59 +1263,53 @@ impl TushareClient {
         
         let internal_request = InternalTushareRequest {
             api_name: ApiNameRef(&request.api_name),
-            token: &self.token,
+            token: lease.token.expose(),
             params: &request.params,
             fields: &request.fields,
         };
 
-        self.logger.log_http_request(&request_id);
-        
-        let response = self.client
-            .post("http://api.tushare.pro")
-            .json(&internal_request)
-            .send()
-            .await
-            .map_err(|e| {
-                let elapsed = start_time.elapsed();
-                self.logger.log_http_error(&request_id, elapsed, &e.to_string());
-                e
-            })?;
+        let body = serde_json::to_string(&internal_request)?;
+        let response = self.send_with_failover(context, start_time, body).await?;
 
-        let status = response.status();
-        self.logger.log_http_response(&request_id, status.as_u16());
-        
-        let response_text = response.text().await
-            .map_err(|e| {
-                let elapsed = start_time.elapsed();
-                self.logger.log_response_read_error(&request_id, elapsed, &e.to_string());
-                e
-            })?;
-        self.logger.log_raw_response(&request_id, &response_text);
+        self.logger.log_http_response(request_id, response.status);
+
+        if !(200..300).contains(&response.status) {
+            let body: String = response.body.chars().take(200).collect();
+            self.logger.log_http_error(request_id, start_time.elapsed(), &format!("HTTP status {}: {}", response.status, body));
+            return Err(TushareError::HttpStatus {
+                status: response.status,
+                retry_after: response.retry_after(),
+                body,
+            });
+        }
+
+        let response_text = response.body;
+        self.logger.log_raw_response(request_id, &response_text);
         
         let tushare_response: TushareResponse = serde_json::from_str(&response_text)
-            .map_err(|e| {
+            .inspect_err(|e| {
                 let elapsed = start_time.elapsed();
-                self.logger.log_json_parse_error(&request_id, elapsed, &e.to_string(), &response_text);
-                e
+                self.logger.log_json_parse_error(request_id, elapsed, &e.to_string(), &response_text);
             })?;
 
         let elapsed = start_time.elapsed();
         
         if tushare_response.code != 0 {
-            let message = format!("error code: {}, error msg: {}", tushare_response.code, tushare_response.msg.clone().unwrap_or_default());
-            self.logger.log_api_error(&request_id, elapsed, tushare_response.code, &message);
+            let message = tushare_response.msg.clone().unwrap_or_default();
+            self.logger.log_api_error(request_id, elapsed, tushare_response.code, &message);
             return Err(TushareError::ApiError {
                 code: tushare_response.code,
-                message
+                message,
+                request_id: tushare_response.request_id,
             });
         }
 
         // Log success information and performance metrics
-        self.logger.log_api_success(&request_id, elapsed, tushare_response.data.clone().map(|data| data.items.len()).unwrap_or(0));
+        self.logger.log_api_success(request_id, elapsed, tushare_response.data.as_ref().map(|data| data.items.len()).unwrap_or(0));
         
         // Log response details (if enabled)
         self.logger.log_response_details(
-            &request_id,
+            request_id,
             &tushare_response.request_id,
             &format!("{:?}", tushare_response.data.as_ref().map(|d| &d.fields))
         );
@@ -514,6 +1317,62 @@ impl TushareClient {
         Ok(tushare_response)
     }
 
+    /// Send the request to the configured endpoints in order, failing over to the
+    /// next endpoint when one fails at the network level.
+    async fn send_with_failover(
+        &self,
+        context: &RequestContext,
+        start_time: Instant,
+        body: String,
+    ) -> TushareResult<TransportResponse> {
+        let request_id = context.request_id.as_str();
+        let mut last_error = None;
+
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            self.logger.log_http_request(request_id);
+
+            let transport_request = TransportRequest {
+                endpoint: endpoint.clone(),
+                body: body.clone(),
+                headers: context.headers.clone(),
+                timeout: context.timeout,
+            };
+            let sent = match context.timeout {
+                // Enforced here as well, so custom transports honor per-call timeouts too
+                Some(timeout) => tokio::time::timeout(timeout, self.transport.send(transport_request))
+                    .await
+                    .unwrap_or(Err(TushareError::TimeoutError)),
+                None => self.transport.send(transport_request).await,
+            };
+            match sent {
+                Ok(response) => {
+                    self.logger.log_endpoint_served(request_id, endpoint, index);
+                    return Ok(response);
+                }
+                Err(e) if is_network_error(&e) => {
+                    let remaining = self.endpoints.len() - index - 1;
+                    if remaining > 0 {
+                        self.logger.log_endpoint_failover(request_id, endpoint, remaining, &e.to_string());
+                    }
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    self.logger.log_http_error(request_id, start_time.elapsed(), &e.to_string());
+                    return Err(e);
+                }
+            }
+        }
+
+        let elapsed = start_time.elapsed();
+        match last_error {
+            Some(e) => {
+                self.logger.log_http_error(request_id, elapsed, &e.to_string());
+                Err(e)
+            }
+            None => Err(TushareError::Other("No API endpoint configured".to_string())),
+        }
+    }
+
     /// 调用 Tushare API，并将响应的 `data.items` 解析为强类型的 [`TushareEntityList<T>`]。
     ///
     /// 这是 [`Self::call_api`] 的便捷封装：先执行请求，再把响应转换为实体列表。
@@ -547,11 +1406,46 @@ impl TushareClient {
         for<'a> <&'a R as TryInto<TushareRequest>>::Error: Into<TushareError>,
     {
         let response = self.call_api(&request).await?;
-        TushareEntityList::try_from(response).map_err(Into::into)
+        TushareEntityList::try_from(response)
+    }
+
+    /// Stream the pages of a paginated API, advancing `offset` by `page_size` rows until a
+    /// page reports `has_more == false`. See [`crate::pagination`].
+    pub fn call_api_paginated<'a, T, R>(
+        &'a self,
+        request: &R,
+        page_size: usize,
+    ) -> impl Stream<Item = TushareResult<TushareEntityList<T>>> + use<'a, T, R>
+    where
+        T: crate::traits::FromTushareData,
+        for<'b> &'b R: TryInto<TushareRequest>,
+        for<'b> <&'b R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        let request = request.try_into().map_err(Into::into);
+        pagination::paginate(request, page_size, move |page| async move { self.call_api::<TushareRequest>(&page).await })
+    }
+
+    /// Like [`Self::call_api_paginated`], flattened into a stream of rows
+    pub fn call_api_paginated_items<'a, T, R>(
+        &'a self,
+        request: &R,
+        page_size: usize,
+    ) -> impl Stream<Item = TushareResult<T>> + use<'a, T, R>
+    where
+        T: crate::traits::FromTushareData,
+        for<'b> &'b R: TryInto<TushareRequest>,
+        for<'b> <&'b R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        pagination::items(self.call_api_paginated(request, page_size))
     }
  }
 
- /// Generate a unique request ID for logging purposes
+/// Whether an error happened at the network level, making endpoint failover worthwhile
+fn is_network_error(err: &TushareError) -> bool {
+    matches!(err, TushareError::HttpError(_) | TushareError::TimeoutError)
+}
+
+/// Generate a unique request ID for logging purposes
 pub(crate) fn generate_request_id() -> String {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -560,35 +1454,191 @@ pub(crate) fn generate_request_id() -> String {
     format!("req_{}", timestamp)
 }
 
- mod tests {
-    use crate::{fields, params, Api, TushareClient, TushareRequest};
+#[cfg(test)]
+mod tests {
+    use super::{ProxyConfig, DEFAULT_BASE_URL};
+    use crate::cache::{CacheConfig, CacheMode};
+    use crate::transport::fixtures::{error_response, rows_response, rows_transport};
+    use crate::transport::{InMemoryTransport, TransportResponse};
+    use crate::{Api, TushareClient, TushareError, TushareRequest};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
 
     #[tokio::test]
-    async fn test() {
-        unsafe { std::env::set_var("TUSHARE_TOKEN", "xxxx"); }
-        let client = TushareClient::from_env().unwrap();
+    async fn call_api_through_in_memory_transport() {
+        let transport = Arc::new(rows_transport(&["ts_code", "name"], vec![json!(["000001.SZ", "平安银行"])]));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+
         let response = client.call_api(&r#"
                    {
                         "api_name": "stock_basic",
-                        "params": { "list_stauts": "L"},
-                        "fields": [ "ts_code",
-                                "symbol",
-                                "name",
-                                "area",
-                                "industry",
-                                "list_date",
-                                "exchange",
-                                "market"]
+                        "params": { "list_status": "L"},
+                        "fields": [ "ts_code", "name"]
                     }
             "#
-        ).await;
-        println!("resposne = {:?}", response);
-        // let parmas = params!(
-        //     "list_status" => "L",
-        //     "limit" => "100"
-        // );
-        // let req = TushareRequest::new(Api::StockBasic, parmas, fields!("ts_code"));
-        // let response = client.call_api(req).await.unwrap();
-        // println!("resposne = {:?}", response);
-    }
-}
\ No newline at end of file
+        ).await.unwrap();
+        assert_eq!(response.data.unwrap().items.len(), 1);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].endpoint, DEFAULT_BASE_URL);
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["api_name"], "stock_basic");
+        assert_eq!(body["token"], "test-token");
+        assert_eq!(body["params"]["list_status"], "L");
+    }
+
+    #[tokio::test]
+    async fn api_error_code_is_reported() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(InMemoryTransport::new(|_| Ok(error_response(40101, "invalid token"))))
+            .build()
+            .unwrap();
+
+        let request = TushareRequest::new(Api::Daily, Vec::<(String, String)>::new(), ["ts_code"]);
+        let err = client.call_api(&request).await.unwrap_err();
+        assert!(matches!(err, TushareError::ApiError { code: 40101, .. }));
+    }
+
+    #[tokio::test]
+    async fn pooled_tokens_rotate_and_fail_over_on_token_errors() {
+        let transport = Arc::new(InMemoryTransport::new(|request| {
+            if request.body.contains("token-bad") {
+                return Ok(error_response(40101, "您的token不对，请确认。"));
+            }
+            Ok(rows_response(&["ts_code"], vec![json!(["000001.SZ"])]))
+        }));
+        let client = TushareClient::builder()
+            .with_labeled_token("bad", "token-bad")
+            .with_labeled_token("good", "token-good")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["ts_code"]);
+        assert!(client.call_api(&request).await.is_err());
+        for _ in 0..3 {
+            client.call_api(&request).await.unwrap();
+        }
+
+        let tokens: Vec<String> = transport
+            .requests()
+            .iter()
+            .map(|r| serde_json::from_str::<serde_json::Value>(&r.body).unwrap()["token"].to_string())
+            .collect();
+        assert_eq!(tokens, ["\"token-bad\"", "\"token-good\"", "\"token-good\"", "\"token-good\""]);
+        assert!(client.token_pool().stats()[0].quarantined_for.is_some());
+    }
+
+    #[tokio::test]
+    async fn cached_responses_skip_the_transport_unless_bypassed() {
+        let transport = Arc::new(rows_transport(&["cal_date"], vec![json!(["20240102"])]));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_cache(CacheConfig::new(16))
+            .build()
+            .unwrap();
+
+        let request = TushareRequest::new(Api::TradeCal, [("start_date", "20240101")], ["cal_date"]);
+        client.call_api(&request).await.unwrap();
+        client.call_api(&request).await.unwrap();
+        assert_eq!(transport.requests().len(), 1);
+
+        client.call_api_with_cache_mode(&request, CacheMode::Bypass).await.unwrap();
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn debug_output_never_contains_the_token() {
+        let client = TushareClient::builder()
+            .with_token("0123456789abcdef-secret")
+            .with_transport(InMemoryTransport::new(|_| Ok(TransportResponse::ok("{}"))))
+            .build()
+            .unwrap();
+        let client_debug = format!("{client:?}");
+        let ex_debug = format!("{:?}", crate::TushareClientEx::new(client));
+        for debug in [client_debug, ex_debug] {
+            assert!(!debug.contains("0123456789abcdef-secret"));
+            assert!(debug.contains("0123****"));
+        }
+    }
+
+    #[test]
+    fn proxy_debug_hides_password() {
+        let proxy = ProxyConfig::new("http://proxy.corp:3128").with_basic_auth("alice", "hunter2");
+        let debug = format!("{proxy:?}");
+        assert!(debug.contains("alice"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    fn http_config_with_proxy_and_certificates_builds() {
+        let config = super::HttpClientConfig::new()
+            .with_proxy(ProxyConfig::new("http://proxy.corp:3128").with_no_proxy("localhost"))
+            .with_root_certificate_pem(Vec::new());
+        assert!(config.build_client().is_ok());
+
+        let missing = super::HttpClientConfig::new().with_root_certificate_file("/nonexistent/ca.pem");
+        assert!(missing.build_client().is_err());
+    }
+
+    /// Serve a single canned JSON response on a local port and return its URL
+    async fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 16 * 1024];
+            let _ = socket.read(&mut buf).await;
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint_on_network_error() {
+        // Reserve a port and release it so that connecting to it is refused
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_url = format!("http://{}", dead.local_addr().unwrap());
+        drop(dead);
+
+        let live_url = serve_once(
+            r#"{"request_id":"r1","code":0,"msg":"","data":{"fields":["ts_code"],"items":[["000001.SZ"]],"has_more":false,"count":1}}"#,
+        )
+        .await;
+
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_endpoints([dead_url.as_str(), live_url.as_str()])
+            .build()
+            .unwrap();
+        assert_eq!(client.endpoints(), &[dead_url.clone(), live_url.clone()]);
+
+        let request = TushareRequest::new(Api::StockBasic, Vec::<(String, String)>::new(), ["ts_code"]);
+        let response = client.call_api(&request).await.unwrap();
+        assert_eq!(response.data.unwrap().items.len(), 1);
+    }
+
+    #[test]
+    fn builder_rejects_empty_endpoint_list() {
+        let result = TushareClient::builder()
+            .with_token("test-token")
+            .with_endpoints(Vec::<String>::new())
+            .build();
+        assert!(result.is_err());
+    }
+}
@@ -1,11 +1,23 @@
-use crate::error::{TushareError, TushareResult};
+use crate::batching::{BatchConfig, Batcher};
+use crate::cache::CacheMode;
+use crate::chunking::{self, ChunkConfig};
+use crate::call_options::CallOptions;
+use crate::concurrency::ConcurrencyLimiter;
+use crate::fan_out::FanOut;
+use crate::circuit_breaker::{CircuitBreaker, CircuitTransition};
+use crate::pagination;
+use crate::rate_limit::RateLimiter;
+use crate::truncation;
+use crate::retry::{DefaultRetryPolicy, RetryContext, RetryDecision, RetryPolicy};
+use crate::error::{ErrorKind, TushareError, TushareResult};
 use crate::types::{TushareEntityList, TushareRequest, TushareResponse};
 use crate::{Api, TushareClient};
-use rand::Rng;
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
-use tokio::sync::Mutex;
-use tokio::time::sleep;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::time::{sleep, sleep_until, Instant};
+use futures::Stream;
 
 /// Retry configuration for [`TushareClientEx`].
 ///
@@ -13,7 +25,7 @@ use tokio::time::sleep;
 /// can stay focused on a single HTTP request + response parsing.
 ///
 /// Notes:
-/// - Only retryable errors will be retried (currently network/timeout errors).
+/// - Applied through [`DefaultRetryPolicy`]; see it for which errors are retried.
 /// - The delay uses exponential backoff: `base_delay * 2^attempt`, capped by `max_delay`.
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
@@ -40,9 +52,25 @@ impl Default for RetryConfig {
 ///   calls to the same API will be automatically delayed so that two calls are at
 ///   least `min_interval` apart. Callers do not need to implement any sleep logic.
 ///
+/// - **Per-API circuit breaker (optional)**
+///   When enabled via [`Self::with_circuit_breaker`], an API that keeps failing at the
+///   network/server level fails fast until it recovers.
+///
+/// - **Bounded concurrency (optional)**
+///   When enabled via [`Self::with_concurrency_limiter`], at most N calls (globally
+///   and/or per API) are in flight at once; the rest queue in FIFO order.
+///
+/// - **Token-bucket rate limiting with daily quotas (optional)**
+///   When enabled via [`Self::with_rate_limiter`], every attempt (including retries)
+///   draws from per-minute budgets that can be shared across clients.
+///
+/// - **Batching of single-code requests (optional)**
+///   When enabled via [`Self::with_batching`], concurrent requests for one `ts_code` each
+///   are merged into multi-code calls and the rows split back to each caller.
+///
 /// - **Retry with exponential backoff (optional)**
-///   When enabled via [`Self::with_retry_config`], network/timeout failures will be
-///   retried with exponential backoff.
+///   When enabled via [`Self::with_retry_config`] or [`Self::with_retry_policy`], network
+///   failures, timeouts, 5xx responses, invalid bodies and throttling replies are retried.
 ///
 /// This wrapper is designed to keep the core client stable while allowing you to
 /// opt into additional behaviors.
@@ -51,7 +79,12 @@ pub struct TushareClientEx {
     inner: TushareClient,
     api_min_intervals: HashMap<String, Duration>,
     api_next_allowed_at: Mutex<HashMap<String, Instant>>,
-    retry: Option<RetryConfig>,
+    retry: Option<Arc<dyn RetryPolicy>>,
+    rate_limiter: Option<RateLimiter>,
+    circuit_breaker: Option<CircuitBreaker>,
+    concurrency: Option<ConcurrencyLimiter>,
+    batcher: Option<Batcher>,
+    auto_split: bool,
 }
 
 impl TushareClientEx {
@@ -64,6 +97,11 @@ impl TushareClientEx {
             api_min_intervals: HashMap::new(),
             api_next_allowed_at: Mutex::new(HashMap::new()),
             retry: None,
+            rate_limiter: None,
+            circuit_breaker: None,
+            concurrency: None,
+            batcher: None,
+            auto_split: false,
         }
     }
 
@@ -88,16 +126,69 @@ impl TushareClientEx {
         self
     }
 
-    /// Enable retry with exponential backoff.
+    /// Enable retry with exponential backoff, using [`DefaultRetryPolicy`].
     ///
     /// Retryable errors:
-    /// - [`TushareError::HttpError`]
-    /// - [`TushareError::TimeoutError`]
+    /// - [`TushareError::HttpError`] and [`TushareError::TimeoutError`]
+    /// - [`TushareError::HttpStatus`] with a 429 or 5xx status (honoring `Retry-After`)
+    /// - [`TushareError::SerializationError`] (truncated or invalid response bodies)
+    /// - [`TushareError::ApiError`] per-minute throttling replies, retried in the next minute window
+    ///
+    /// Other [`TushareError::ApiError`]s (hourly or daily caps, permissions, parameters,
+    /// token) are not retried.
+    pub fn with_retry_config(self, config: RetryConfig) -> Self {
+        self.with_retry_policy(DefaultRetryPolicy::from(config))
+    }
+
+    /// Enable retry with a custom [`RetryPolicy`].
+    pub fn with_retry_policy<P: RetryPolicy + 'static>(mut self, policy: P) -> Self {
+        self.retry = Some(Arc::new(policy));
+        self
+    }
+
+    /// Limit calls with a token-bucket [`RateLimiter`].
+    ///
+    /// Unlike [`Self::with_api_min_interval`], which spaces calls evenly, the limiter allows
+    /// bursts up to each budget and enforces daily quotas, failing with
+    /// [`TushareError::QuotaExhausted`] once one is used up. Pass clones of the same limiter
+    /// to several clients to make them share one budget.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Fail fast on APIs that keep failing, using a per-API [`CircuitBreaker`].
+    ///
+    /// While an API's circuit is open, calls fail with [`TushareError::CircuitOpen`]
+    /// without consuming rate-limit budget; the default retry policy does not retry them.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Bound the number of in-flight calls with a [`ConcurrencyLimiter`].
     ///
-    /// Non-retryable errors (by design):
-    /// - [`TushareError::ApiError`] (business-level errors returned by Tushare)
-    pub fn with_retry_config(mut self, config: RetryConfig) -> Self {
-        self.retry = Some(config);
+    /// Each attempt holds a slot from before its rate-limit wait until its response is
+    /// parsed. Queue wait times are available from
+    /// [`ConcurrencyLimiter::queue_wait_stats`].
+    pub fn with_concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.concurrency = Some(limiter);
+        self
+    }
+
+    /// Merge concurrent single-code requests of the configured APIs into multi-code calls.
+    ///
+    /// Each merged call goes through rate limiting and retries once, on behalf of all the
+    /// requests it serves. See [`crate::batching`].
+    pub fn with_batching(mut self, config: BatchConfig) -> Self {
+        self.batcher = Some(Batcher::new(config));
+        self
+    }
+
+    /// Re-issue responses that reach their API's row cap in two halves, split by `ts_code`
+    /// list or by date range, and merge the halves. See [`crate::truncation`].
+    pub fn with_auto_split(mut self, enabled: bool) -> Self {
+        self.auto_split = enabled;
         self
     }
 
@@ -113,15 +204,104 @@ impl TushareClientEx {
 
     /// Call API with configured rate limiting (sleep) and optional retry.
     pub async fn call_api<T>(&self, request: &T) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.call_api_with_cache_mode(request, CacheMode::Use).await
+    }
+
+    /// Call API choosing how this call interacts with the inner client's response cache.
+    ///
+    /// The inner client looks the cache up after its middlewares have run, on each attempt.
+    pub async fn call_api_with_cache_mode<T>(&self, request: &T, cache_mode: CacheMode) -> TushareResult<TushareResponse>
+    where
+        for<'a> &'a T: TryInto<TushareRequest>,
+        for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        self.call_api_with(request, CallOptions::new().with_cache_mode(cache_mode)).await
+    }
+
+    /// Call API with per-call options.
+    ///
+    /// The deadline and cancellation token of `options` cover the whole call: rate-limit
+    /// sleeps, every retry attempt and the backoff delays between them. The timeout
+    /// applies to each attempt.
+    pub async fn call_api_with<T>(&self, request: &T, options: CallOptions) -> TushareResult<TushareResponse>
     where
         for<'a> &'a T: TryInto<TushareRequest>,
         for<'a> <&'a T as TryInto<TushareRequest>>::Error: Into<TushareError>,
     {
         let request = request.try_into().map_err(Into::into)?;
+        self.call_request_counting_retries(request, options, &AtomicUsize::new(0)).await
+    }
+
+    /// Call `request` without automatic splitting, for callers that split windows themselves
+    /// ([`crate::chunking`])
+    pub(crate) async fn call_request_without_split(&self, request: &TushareRequest) -> TushareResult<TushareResponse> {
+        let request_id = crate::client::generate_request_id();
+        self.call_request_unsplit(&request_id, request.clone(), CallOptions::default(), &AtomicUsize::new(0))
+            .await
+    }
+
+    /// [`Self::call_api_with`] for a built request, adding the retries made to `retries`
+    pub(crate) async fn call_request_counting_retries(
+        &self,
+        request: TushareRequest,
+        options: CallOptions,
+        retries: &AtomicUsize,
+    ) -> TushareResult<TushareResponse> {
+        let request_id = crate::client::generate_request_id();
+        // Pages are full by design; splitting one would re-apply its offset to each half
+        if !self.auto_split || pagination::is_paged(&request) {
+            return self.call_request_unsplit(&request_id, request, options, retries).await;
+        }
 
-        self.apply_api_min_interval_rate_limit(&request.api_name.name()).await;
+        let result = self
+            .call_request_unsplit(&request_id, request.clone(), options.clone(), retries)
+            .await;
+        let truncated = match &result {
+            Ok(response) => self
+                .inner
+                .row_cap(&request.api_name)
+                .zip(response.data.as_ref())
+                .is_some_and(|(row_cap, data)| data.items.len() >= row_cap),
+            Err(err) => err.kind() == ErrorKind::PossiblyTruncated,
+        };
+        let Some((first, second)) = truncated.then(|| truncation::split_request(&request)).flatten() else {
+            return result;
+        };
 
-        self.call_api_with_retry(request).await
+        self.inner.logger().log_truncation_split(&request_id, &request.api_name.name());
+        let first = Box::pin(self.call_request_counting_retries(first, options.clone(), retries)).await?;
+        let second = Box::pin(self.call_request_counting_retries(second, options, retries)).await?;
+        Ok(truncation::merge_halves(first, second))
+    }
+
+    async fn call_request_unsplit(
+        &self,
+        request_id: &str,
+        request: TushareRequest,
+        options: CallOptions,
+        retries: &AtomicUsize,
+    ) -> TushareResult<TushareResponse> {
+        let call = async {
+            let send = |request: TushareRequest| async {
+                self.apply_api_min_interval_rate_limit(&request.api_name.name()).await;
+                self.call_api_with_retry(request_id, request, &options, retries).await
+            };
+            match &self.batcher {
+                Some(batcher) => match batcher.batch_code(&request) {
+                    Some(code) => {
+                        let row_cap = self.inner.row_cap(&request.api_name);
+                        batcher.run(self.inner.logger(), request_id, request, code, row_cap, send).await
+                    }
+                    None => send(request).await,
+                },
+                None => send(request).await,
+            }
+        };
+        options.run(self.inner.logger(), request_id, call).await
     }
 
     pub async fn call_api_as<T, R>(&self, request: &R) -> TushareResult<TushareEntityList<T>>
@@ -131,62 +311,172 @@ impl TushareClientEx {
         for<'a> <&'a R as TryInto<TushareRequest>>::Error: Into<TushareError>,
     {
         let response = self.call_api(request).await?;
-        TushareEntityList::try_from(response).map_err(Into::into)
+        TushareEntityList::try_from(response)
+    }
+
+    /// Fetch a long `start_date`..=`end_date` range window by window and merge the rows in
+    /// date order. See [`crate::chunking`].
+    pub async fn call_api_chunked<T, R>(&self, request: &R, config: &ChunkConfig) -> TushareResult<TushareEntityList<T>>
+    where
+        T: crate::traits::FromTushareData,
+        for<'a> &'a R: TryInto<TushareRequest>,
+        for<'a> <&'a R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        let request = request.try_into().map_err(Into::into)?;
+        chunking::fetch_chunked(self, request, config).await
     }
 
-    async fn call_api_with_retry(&self, request: TushareRequest) -> TushareResult<TushareResponse> {
-        let Some(cfg) = self.retry.clone() else {
-            return self.inner.call_api_request(&request).await;
+    /// Run `template` once per parameter override, at most `concurrency` calls at a time.
+    /// Items come back in input order; failures do not stop the batch. See [`crate::fan_out`].
+    pub fn fan_out<'a, I, P, K, V>(&'a self, template: &TushareRequest, overrides: I, concurrency: usize) -> FanOut<'a, P>
+    where
+        I: IntoIterator<Item = P>,
+        I::IntoIter: Send + 'a,
+        P: IntoIterator<Item = (K, V)> + Clone + Send + 'a,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        FanOut::new(self, template.clone(), overrides, concurrency)
+    }
+
+    /// Stream the pages of a paginated API, advancing `offset` by `page_size` rows until a
+    /// page reports `has_more == false`. See [`crate::pagination`].
+    pub fn call_api_paginated<'a, T, R>(
+        &'a self,
+        request: &R,
+        page_size: usize,
+    ) -> impl Stream<Item = TushareResult<TushareEntityList<T>>> + use<'a, T, R>
+    where
+        T: crate::traits::FromTushareData,
+        for<'b> &'b R: TryInto<TushareRequest>,
+        for<'b> <&'b R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        let request = request.try_into().map_err(Into::into);
+        pagination::paginate(request, page_size, move |page| async move { self.call_api::<TushareRequest>(&page).await })
+    }
+
+    /// Like [`Self::call_api_paginated`], flattened into a stream of rows
+    pub fn call_api_paginated_items<'a, T, R>(
+        &'a self,
+        request: &R,
+        page_size: usize,
+    ) -> impl Stream<Item = TushareResult<T>> + use<'a, T, R>
+    where
+        T: crate::traits::FromTushareData,
+        for<'b> &'b R: TryInto<TushareRequest>,
+        for<'b> <&'b R as TryInto<TushareRequest>>::Error: Into<TushareError>,
+    {
+        pagination::items(self.call_api_paginated(request, page_size))
+    }
+
+    async fn call_api_with_retry(
+        &self,
+        request_id: &str,
+        request: TushareRequest,
+        options: &CallOptions,
+        retry_count: &AtomicUsize,
+    ) -> TushareResult<TushareResponse> {
+        let Some(policy) = self.retry.as_ref() else {
+            return self.call_api_once(request_id, &request, options).await;
         };
 
-        let mut attempt = 0usize;
-        let request_id = crate::client::generate_request_id();
+        let started = Instant::now();
+        let mut retries = 0usize;
         let api_name = request.api_name.name();
 
         loop {
-            match self
-                .inner
-                .call_api_request_with_request_id(&request_id, &request)
-                .await
-            {
+            let err = match self.call_api_once(request_id, &request, options).await {
                 Ok(resp) => return Ok(resp),
-                Err(err) => {
-                    let should_retry = attempt < cfg.max_retries && is_retryable_error(&err);
-                    if !should_retry {
-                        self.inner.logger().log_safe(
-                            crate::logging::LogLevel::Error,
-                            || {
-                                format!(
-                                    "[{}] tushare_api retry exhausted or non-retryable error; api={}, attempts={}, max_retries={}, err={}",
-                                    request_id, api_name, attempt, cfg.max_retries, err
-                                )
-                            },
-                            None,
-                        );
-                        return Err(err);
-                    }
+                Err(err) => err,
+            };
 
-                    let delay = compute_backoff_delay(&cfg, attempt);
-                    self.inner.logger().log_safe(
-                        crate::logging::LogLevel::Warn,
-                        || {
-                            format!(
-                                "[{}] tushare_api retrying; api={}, retry={}/{}, delay={:?}, err={}",
-                                request_id,
-                                api_name,
-                                attempt + 1,
-                                cfg.max_retries,
-                                delay,
-                                err
-                            )
-                        },
-                        None,
-                    );
-                    sleep(delay).await;
-                    attempt += 1;
+            let context = RetryContext {
+                retries,
+                elapsed: started.elapsed(),
+                error: &err,
+            };
+            let RetryDecision::Retry(delay) = policy.decide(&context) else {
+                self.inner.logger().log_safe(
+                    crate::logging::LogLevel::Error,
+                    || {
+                        format!(
+                            "[{}] tushare_api retry exhausted or non-retryable error; api={}, retries={}, err={}",
+                            request_id, api_name, retries, err
+                        )
+                    },
+                    None,
+                );
+                return Err(err);
+            };
+
+            self.inner.logger().log_safe(
+                crate::logging::LogLevel::Warn,
+                || {
+                    format!(
+                        "[{}] tushare_api retrying; api={}, retry={}, delay={:?}, err={}",
+                        request_id,
+                        api_name,
+                        retries + 1,
+                        delay,
+                        err
+                    )
+                },
+                None,
+            );
+            sleep(delay).await;
+            retries += 1;
+            retry_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// One attempt, after drawing from the rate limiter's budget
+    async fn call_api_once(
+        &self,
+        request_id: &str,
+        request: &TushareRequest,
+        options: &CallOptions,
+    ) -> TushareResult<TushareResponse> {
+        let api_name = request.api_name.name();
+        if let Some(breaker) = &self.circuit_breaker {
+            breaker.check(&api_name)?;
+        }
+        let _permit = match &self.concurrency {
+            Some(limiter) => {
+                let permit = limiter.acquire(&api_name).await;
+                if permit.was_queued() {
+                    self.inner.logger().log_queue_wait(request_id, &api_name, permit.waited);
                 }
+                Some(permit)
+            }
+            None => None,
+        };
+        if let Some(limiter) = &self.rate_limiter {
+            let waited = limiter.acquire(&api_name).await?;
+            if !waited.is_zero() {
+                self.inner.logger().log_rate_limit_wait(request_id, &api_name, waited);
             }
         }
+        // Taken after the waits, so a probe is only admitted once it is about to be sent
+        if let Some(breaker) = &self.circuit_breaker {
+            let transition = breaker.try_acquire(&api_name)?;
+            self.log_circuit_transition(request_id, &api_name, transition);
+        }
+
+        let result = self.inner.call_api_request_with_request_id(request_id, request, options).await;
+
+        if let Some(breaker) = &self.circuit_breaker {
+            let transition = breaker.record(&api_name, &result);
+            self.log_circuit_transition(request_id, &api_name, transition);
+        }
+        result
+    }
+
+    fn log_circuit_transition(&self, request_id: &str, api_name: &str, transition: Option<CircuitTransition>) {
+        if let Some(CircuitTransition { from, to }) = transition {
+            self.inner
+                .logger()
+                .log_circuit_transition(request_id, api_name, &from.to_string(), &to.to_string());
+        }
     }
 
     async fn apply_api_min_interval_rate_limit(&self, api_name: &str) {
@@ -194,47 +484,257 @@ impl TushareClientEx {
             return;
         };
 
+        // Reserve the next free slot up front, so concurrent callers are served in FIFO order
         let now = Instant::now();
-        let wait = {
-            let mut guard = self.api_next_allowed_at.lock().await;
-            let next_allowed_at = guard.get(api_name).copied().unwrap_or(now);
-            let base = if next_allowed_at > now { next_allowed_at } else { now };
-            guard.insert(api_name.to_string(), base + min_interval);
-            if base > now {
-                base - now
-            } else {
-                Duration::from_secs(0)
-            }
+        let slot = {
+            let mut next_allowed_at = self.api_next_allowed_at.lock().unwrap();
+            let slot = next_allowed_at.get(api_name).map_or(now, |next| (*next).max(now));
+            next_allowed_at.insert(api_name.to_string(), slot + min_interval);
+            slot
         };
 
-        if !wait.is_zero() {
-            sleep(wait).await;
+        if slot > now {
+            let reservation = IntervalReservation {
+                next_allowed_at: &self.api_next_allowed_at,
+                api_name,
+                slot,
+                min_interval,
+                used: false,
+            };
+            sleep_until(slot).await;
+            reservation.use_slot();
         }
     }
 }
 
-fn is_retryable_error(err: &TushareError) -> bool {
-    matches!(
-        err,
-        TushareError::HttpError(_) | TushareError::TimeoutError
-    )
+/// Slot reserved by a call waiting out a minimum interval; given back if the call is dropped first
+struct IntervalReservation<'a> {
+    next_allowed_at: &'a Mutex<HashMap<String, Instant>>,
+    api_name: &'a str,
+    slot: Instant,
+    min_interval: Duration,
+    used: bool,
 }
 
-fn compute_backoff_delay(cfg: &RetryConfig, attempt: usize) -> Duration {
-    let shift = attempt.min(31) as u32;
-    let factor = 1u64.checked_shl(shift).unwrap_or(u64::MAX);
-    let base = cfg.base_delay.saturating_mul(factor as u32);
-    let capped = if base > cfg.max_delay { cfg.max_delay } else { base };
-
-    // Equal jitter: capped/2 + random(0..=capped/2)
-    // Compared to full jitter, this is less volatile while still spreading retries.
-    let capped_ms = capped.as_millis().min(u64::MAX as u128) as u64;
-    if capped_ms == 0 {
-        return Duration::from_millis(0);
+impl IntervalReservation<'_> {
+    fn use_slot(mut self) {
+        self.used = true;
     }
+}
 
-    let half = capped_ms / 2;
-    let jitter_ms = rand::thread_rng().gen_range(0..=half);
-    Duration::from_millis(half + jitter_ms)
+impl Drop for IntervalReservation<'_> {
+    fn drop(&mut self) {
+        if self.used {
+            return;
+        }
+        // Only the last reservation can be handed back; slots queued behind it stay put
+        let mut next_allowed_at = self.next_allowed_at.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(next) = next_allowed_at.get_mut(self.api_name)
+            && *next == self.slot + self.min_interval
+        {
+            *next = self.slot;
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheConfig;
+    use crate::call_options::CancellationToken;
+    use crate::middleware::{Middleware, RequestContext};
+    use crate::circuit_breaker::{CircuitBreakerConfig, CircuitState};
+    use crate::rate_limit::RateLimitConfig;
+    use crate::transport::fixtures::{rows_response, rows_transport};
+    use crate::transport::{BoxFuture, InMemoryTransport, TransportResponse};
+    use serde_json::json;
+    use std::sync::atomic::AtomicBool;
+
+    /// Adds `exchange=SSE` to every request
+    #[derive(Debug)]
+    struct DefaultExchange;
+
+    impl Middleware for DefaultExchange {
+        fn before<'a>(
+            &'a self,
+            _context: &'a mut RequestContext,
+            request: &'a mut TushareRequest,
+        ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+            request.params.entry("exchange".to_string()).or_insert_with(|| "SSE".to_string());
+            Box::pin(async { Ok(None) })
+        }
+    }
+
+    /// Rejects every request once switched on
+    #[derive(Debug, Default)]
+    struct Gate {
+        closed: AtomicBool,
+    }
+
+    impl Middleware for Gate {
+        fn before<'a>(
+            &'a self,
+            _context: &'a mut RequestContext,
+            _request: &'a mut TushareRequest,
+        ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+            let result = if self.closed.load(Ordering::SeqCst) {
+                Err(TushareError::Other("gate closed".to_string()))
+            } else {
+                Ok(None)
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_hits_go_through_the_middlewares() {
+        let transport = Arc::new(rows_transport(&["cal_date"], vec![json!(["20240102"])]));
+        let gate = Arc::new(Gate::default());
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_cache(CacheConfig::new(16))
+            .with_middleware(DefaultExchange)
+            .with_middleware(gate.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let request = TushareRequest::new(Api::TradeCal, [("start_date", "20240101")], ["cal_date"]);
+
+        // Stored under the rewritten request, so the second call hits
+        client.call_api(&request).await.unwrap();
+        client.call_api(&request).await.unwrap();
+        assert_eq!(transport.requests().len(), 1);
+        assert!(transport.requests()[0].body.contains(r#""exchange":"SSE""#));
+
+        gate.closed.store(true, Ordering::SeqCst);
+        let result = client.call_api(&request).await;
+        assert!(matches!(result, Err(TushareError::Other(_))));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_bad_gateway_then_succeeds() {
+        let transport = Arc::new(InMemoryTransport::new({
+            let calls = std::sync::atomic::AtomicUsize::new(0);
+            move |_| {
+                // The first two calls hit a failing gateway
+                if calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                    return Ok(TransportResponse::new(502, "<html>Bad Gateway</html>").with_header("Retry-After", "0"));
+                }
+                Ok(rows_response(&["close"], vec![json!([10.5])]))
+            }
+        }));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["close"]);
+
+        let without_retry = TushareClientEx::new(client);
+        let err = without_retry.call_api(&request).await.unwrap_err();
+        assert!(matches!(err, TushareError::HttpStatus { status: 502, .. }));
+
+        let with_retry = TushareClientEx::new(without_retry.into_inner()).with_retry_config(RetryConfig::default());
+        let response = with_retry.call_api(&request).await.unwrap();
+        assert_eq!(response.data.unwrap().items.len(), 1);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn cancellation_interrupts_rate_limit_sleep() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(rows_transport(&["close"], vec![json!([10.5])]))
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_api_min_interval(Api::Daily, Duration::from_secs(60));
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["close"]);
+        client.call_api(&request).await.unwrap();
+
+        let token = CancellationToken::new();
+        let canceller = {
+            let token = token.clone();
+            tokio::spawn(async move {
+                sleep(Duration::from_millis(20)).await;
+                token.cancel();
+            })
+        };
+        let started = Instant::now();
+        let result = client.call_api_with(&request, CallOptions::new().with_cancellation(token)).await;
+        assert!(matches!(result, Err(TushareError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        canceller.await.unwrap();
+
+        let result = client
+            .call_api_with(&request, CallOptions::new().with_deadline_in(Duration::from_millis(20)))
+            .await;
+        assert!(matches!(result, Err(TushareError::TimeoutError)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn aborted_calls_release_their_interval_slot() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(rows_transport(&["close"], vec![json!([10.5])]))
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_api_min_interval(Api::Daily, Duration::from_millis(300));
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["close"]);
+        let started = Instant::now();
+        client.call_api(&request).await.unwrap();
+
+        let result = client
+            .call_api_with(&request, CallOptions::new().with_deadline_in(Duration::from_millis(20)))
+            .await;
+        assert!(matches!(result, Err(TushareError::TimeoutError)));
+
+        // Runs in the slot the aborted call gave up, not the one after it
+        client.call_api(&request).await.unwrap();
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn quota_errors_while_half_open_leave_the_probe_to_the_next_call() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let transport = Arc::new(InMemoryTransport::new({
+            let failing = failing.clone();
+            move |_| {
+                if failing.load(Ordering::SeqCst) {
+                    return Ok(TransportResponse::new(502, "<html>Bad Gateway</html>"));
+                }
+                Ok(rows_response(&["close"], vec![json!([10.5])]))
+            }
+        }));
+        let breaker = CircuitBreaker::new(
+            CircuitBreakerConfig::default()
+                .with_consecutive_failures(1)
+                .with_cooldown(Duration::from_millis(50)),
+        );
+        let build = || {
+            TushareClient::builder()
+                .with_token("test-token")
+                .with_transport(transport.clone())
+                .build()
+                .unwrap()
+        };
+        let client = TushareClientEx::new(build()).with_circuit_breaker(breaker.clone());
+        let exhausted = TushareClientEx::new(build())
+            .with_circuit_breaker(breaker.clone())
+            .with_rate_limiter(RateLimiter::new(RateLimitConfig::new().with_daily_quota(Api::Daily, 0)));
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["close"]);
+
+        assert!(client.call_api(&request).await.is_err());
+        assert_eq!(breaker.state("daily"), CircuitState::Open);
+        failing.store(false, Ordering::SeqCst);
+        sleep(Duration::from_millis(60)).await;
+
+        // The limiter fails the call before it would take the probe
+        let err = exhausted.call_api(&request).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::QuotaExhausted);
+        client.call_api(&request).await.unwrap();
+        assert_eq!(breaker.state("daily"), CircuitState::Closed);
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+//! Global and per-API limits on in-flight requests
+//!
+//! Rate limits space calls out over time; a [`ConcurrencyLimiter`] bounds how many calls run
+//! at once, so firing thousands of requests with `join_all` does not open thousands of
+//! sockets. Waiting calls are admitted in FIFO order (tokio semaphores are fair), and the
+//! time spent waiting is recorded in [`QueueWaitStats`].
+//!
+//! A call first takes a slot of its API (if that API has a limit), then a global slot, so a
+//! call never holds a global slot while queueing behind other calls of its API. Like the
+//! other limiters, `ConcurrencyLimiter` is a cloneable handle shared by its clones.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::{Api, TushareClient, TushareClientEx};
+//! use tushare_api::concurrency::{ConcurrencyConfig, ConcurrencyLimiter};
+//!
+//! # fn build(inner: TushareClient) -> TushareClientEx {
+//! let limiter = ConcurrencyLimiter::new(
+//!     ConcurrencyConfig::new()
+//!         .with_global_limit(16)
+//!         .with_api_limit(Api::Daily, 4),
+//! );
+//! TushareClientEx::new(inner).with_concurrency_limiter(limiter)
+//! # }
+//! ```
+
+use crate::api::Api;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Maximum number of in-flight calls, globally and per API
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyConfig {
+    /// Limit shared by all APIs
+    pub global: Option<usize>,
+    /// Per-API limits, keyed by API name
+    pub api_limits: HashMap<String, usize>,
+}
+
+impl ConcurrencyConfig {
+    /// Configuration without limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow at most `limit` calls in flight across all APIs
+    pub fn with_global_limit(mut self, limit: usize) -> Self {
+        self.global = Some(limit.max(1));
+        self
+    }
+
+    /// Allow at most `limit` calls of `api` in flight
+    pub fn with_api_limit(mut self, api: Api, limit: usize) -> Self {
+        self.api_limits.insert(api.name(), limit.max(1));
+        self
+    }
+}
+
+/// How long calls waited for a slot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueWaitStats {
+    /// Calls admitted
+    pub calls: u64,
+    /// Calls that had to wait for a slot
+    pub queued_calls: u64,
+    /// Total time spent waiting
+    pub total_wait: Duration,
+    /// Longest single wait
+    pub max_wait: Duration,
+}
+
+impl QueueWaitStats {
+    /// Mean wait over all admitted calls
+    pub fn mean_wait(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        self.total_wait / self.calls.min(u32::MAX as u64) as u32
+    }
+
+    fn record(&mut self, waited: Duration) {
+        self.calls += 1;
+        if waited >= QUEUED_THRESHOLD {
+            self.queued_calls += 1;
+        }
+        self.total_wait += waited;
+        self.max_wait = self.max_wait.max(waited);
+    }
+}
+
+/// Waits shorter than this are not counted as queueing
+const QUEUED_THRESHOLD: Duration = Duration::from_millis(1);
+
+/// Slots held by one in-flight call; released on drop
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    _api: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+    /// Time the call waited for its slots
+    pub waited: Duration,
+}
+
+impl ConcurrencyPermit {
+    /// Whether the call had to queue for a slot
+    pub fn was_queued(&self) -> bool {
+        self.waited >= QUEUED_THRESHOLD
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: ConcurrencyConfig,
+    global: Option<Arc<Semaphore>>,
+    apis: HashMap<String, Arc<Semaphore>>,
+    stats: Mutex<(QueueWaitStats, HashMap<String, QueueWaitStats>)>,
+}
+
+/// Shareable limiter of in-flight calls; clones share the same slots
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    shared: Arc<Shared>,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter with all slots free
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        let global = config.global.map(|limit| Arc::new(Semaphore::new(limit)));
+        let apis = config
+            .api_limits
+            .iter()
+            .map(|(api_name, limit)| (api_name.clone(), Arc::new(Semaphore::new(*limit))))
+            .collect();
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                global,
+                apis,
+                stats: Mutex::new(Default::default()),
+            }),
+        }
+    }
+
+    /// Limiter configuration
+    pub fn config(&self) -> &ConcurrencyConfig {
+        &self.shared.config
+    }
+
+    /// Wait for a slot for a call of `api_name`; the slot is held until the permit is dropped
+    pub async fn acquire(&self, api_name: &str) -> ConcurrencyPermit {
+        let started = Instant::now();
+        let api = match self.shared.apis.get(api_name) {
+            Some(semaphore) => Some(acquire_owned(semaphore).await),
+            None => None,
+        };
+        let global = match &self.shared.global {
+            Some(semaphore) => Some(acquire_owned(semaphore).await),
+            None => None,
+        };
+        let waited = started.elapsed();
+
+        let mut stats = self.shared.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stats.0.record(waited);
+        stats.1.entry(api_name.to_string()).or_default().record(waited);
+
+        ConcurrencyPermit {
+            _api: api,
+            _global: global,
+            waited,
+        }
+    }
+
+    /// Queue wait statistics over all APIs
+    pub fn queue_wait_stats(&self) -> QueueWaitStats {
+        self.shared.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).0
+    }
+
+    /// Queue wait statistics of one API
+    pub fn api_queue_wait_stats(&self, api_name: &str) -> QueueWaitStats {
+        let stats = self.shared.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stats.1.get(api_name).copied().unwrap_or_default()
+    }
+
+    /// Global slots currently free, if a global limit is set
+    pub fn available_global_slots(&self) -> Option<usize> {
+        self.shared.global.as_ref().map(|semaphore| semaphore.available_permits())
+    }
+}
+
+async fn acquire_owned(semaphore: &Arc<Semaphore>) -> OwnedSemaphorePermit {
+    semaphore
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("concurrency semaphores are never closed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn limits_in_flight_calls_and_records_waits() {
+        let limiter = ConcurrencyLimiter::new(
+            ConcurrencyConfig::new()
+                .with_global_limit(3)
+                .with_api_limit(Api::Daily, 1),
+        );
+
+        let first = limiter.acquire("daily").await;
+        let second = limiter.acquire("weekly").await;
+        assert_eq!(limiter.available_global_slots(), Some(1));
+
+        let queued = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("daily").await.waited })
+        };
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!queued.is_finished());
+
+        drop(first);
+        let waited = queued.await.unwrap();
+        assert!(waited >= Duration::from_millis(20));
+        drop(second);
+
+        let daily = limiter.api_queue_wait_stats("daily");
+        assert_eq!((daily.calls, daily.queued_calls), (2, 1));
+        assert_eq!(limiter.queue_wait_stats().calls, 3);
+        assert_eq!(limiter.available_global_slots(), Some(3));
+    }
+}
@@ -0,0 +1,156 @@
+//! Helpers for Tushare's `YYYYMMDD` date strings
+//!
+//! Tushare takes and returns dates as `YYYYMMDD` strings. These helpers convert them to day
+//! numbers (days since 1970-01-01) for arithmetic, without pulling in a date crate, and fetch
+//! the trading calendar for calendar-aware helpers such as [`crate::chunking`].
+
+use crate::api::Api;
+use crate::client_ex::TushareClientEx;
+use crate::error::{TushareError, TushareResult};
+use crate::types::TushareRequest;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds Beijing time is ahead of UTC
+const BEIJING_OFFSET_SECS: u64 = 8 * 3600;
+
+/// Parse a `YYYYMMDD` date into days since 1970-01-01
+pub fn parse_date(date: &str) -> TushareResult<i64> {
+    let invalid = || TushareError::ParseError(format!("Invalid date '{date}', expected YYYYMMDD"));
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let year: i64 = date[0..4].parse().map_err(|_| invalid())?;
+    let month: u32 = date[4..6].parse().map_err(|_| invalid())?;
+    let day: u32 = date[6..8].parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day))
+}
+
+/// Format days since 1970-01-01 as `YYYYMMDD`
+pub fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}{month:02}{day:02}")
+}
+
+/// Add `days` (possibly negative) to a `YYYYMMDD` date
+pub fn add_days(date: &str, days: i64) -> TushareResult<String> {
+    Ok(format_date(parse_date(date)? + days))
+}
+
+/// Today's date in Beijing time, as `YYYYMMDD`
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + BEIJING_OFFSET_SECS;
+    format_date((secs / 86_400) as i64)
+}
+
+/// Open trading days of `exchange` (e.g. `SSE`) between `start_date` and `end_date`
+/// inclusive, in ascending order, read from the `trade_cal` API
+pub async fn trade_days(
+    client: &TushareClientEx,
+    exchange: &str,
+    start_date: &str,
+    end_date: &str,
+) -> TushareResult<Vec<String>> {
+    let request = TushareRequest::new(
+        Api::TradeCal,
+        [
+            ("exchange", exchange),
+            ("start_date", start_date),
+            ("end_date", end_date),
+            ("is_open", "1"),
+        ],
+        ["cal_date", "is_open"],
+    );
+    let response = client.call_api(&request).await?;
+    let Some(data) = response.data else {
+        return Err(TushareError::ParseError("Missing data in response".to_string()));
+    };
+
+    let cal_date = column(&data.fields, "cal_date")?;
+    let is_open = data.fields.iter().position(|field| field == "is_open");
+    let mut days: Vec<String> = data
+        .items
+        .iter()
+        .filter(|row| is_open.is_none_or(|i| row.get(i).is_some_and(is_truthy)))
+        .filter_map(|row| row.get(cal_date).and_then(|value| value.as_str()).map(str::to_string))
+        .collect();
+    days.sort();
+    days.dedup();
+    Ok(days)
+}
+
+fn column(fields: &[String], name: &str) -> TushareResult<usize> {
+    fields
+        .iter()
+        .position(|field| field == name)
+        .ok_or_else(|| TushareError::ParseError(format!("Missing field '{name}' in response")))
+}
+
+/// `is_open` comes back as `1`/`0`, as a number or a string
+fn is_truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Number(n) => n.as_i64() == Some(1),
+        serde_json::Value::String(s) => s == "1",
+        serde_json::Value::Bool(b) => *b,
+        _ => false,
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_dates_to_day_numbers_and_back() {
+        assert_eq!(parse_date("19700101").unwrap(), 0);
+        assert_eq!(parse_date("20240301").unwrap() - parse_date("20240228").unwrap(), 2);
+        assert_eq!(add_days("20231231", 1).unwrap(), "20240101");
+        assert_eq!(add_days("20240101", -1).unwrap(), "20231231");
+        assert_eq!(format_date(parse_date("20000229").unwrap()), "20000229");
+        assert!(parse_date("20230229").is_err());
+        assert!(parse_date("2024-01-01").is_err());
+    }
+}
@@ -1,23 +1,126 @@
 use std::fmt;
 use std::error::Error as StdError;
 use std::convert::Infallible;
+use std::sync::Arc;
+
+/// Broad category of a [`TushareError`], for deciding how to react to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The token is invalid or expired (code 40101)
+    InvalidToken,
+    /// A per-minute call limit was hit (code 40203 with a per-minute message, or HTTP 429)
+    RateLimited,
+    /// The account lacks the points or permission to call the API (code 40203)
+    PermissionDenied,
+    /// The request parameters were rejected
+    InvalidParameter,
+    /// The server failed with an HTTP 5xx status
+    ServerError,
+    /// Any other non-zero API code
+    OtherApi,
+    /// Network-level HTTP failure
+    Network,
+    /// Request timeout or deadline exceeded
+    Timeout,
+    /// The call was cancelled
+    Cancelled,
+    /// A daily quota of the local rate limiter is used up, or Tushare reported an hourly
+    /// or daily call limit (code 40203 with a per-hour or per-day message)
+    QuotaExhausted,
+    /// The API's circuit breaker is open
+    CircuitOpen,
+    /// The response reached the API's row cap and may be missing rows
+    PossiblyTruncated,
+    /// JSON (de)serialization failure
+    Serialization,
+    /// Response data could not be converted
+    Parse,
+    /// Anything else
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify a non-zero Tushare response code and its message
+    pub fn from_api_code(code: i32, message: &str) -> Self {
+        match code {
+            40101 => ErrorKind::InvalidToken,
+            40203 if is_quota_message(message) => ErrorKind::QuotaExhausted,
+            40203 if is_rate_limit_message(message) => ErrorKind::RateLimited,
+            40203 => ErrorKind::PermissionDenied,
+            40001 => ErrorKind::InvalidParameter,
+            _ => ErrorKind::OtherApi,
+        }
+    }
+}
+
+/// Rate-limit replies share code 40203 with permission errors, e.g.
+/// "抱歉，您每分钟最多访问该接口500次".
+fn is_rate_limit_message(message: &str) -> bool {
+    ["最多访问", "每分钟"].iter().any(|marker| message.contains(marker))
+}
+
+/// Hourly and daily caps, e.g. "抱歉，您每天最多访问该接口100000次", do not reset
+/// within the next minute
+fn is_quota_message(message: &str) -> bool {
+    ["每小时", "每天"].iter().any(|marker| message.contains(marker))
+}
 
 /// Tushare API error types
 #[derive(Debug)]
 pub enum TushareError {
     /// HTTP request error
     HttpError(reqwest::Error),
-    /// API response error (contains error code and error message)
+    /// Non-2xx HTTP status, e.g. a 502 page from a gateway
+    HttpStatus {
+        status: u16,
+        /// Delay from the `Retry-After` header, if present
+        retry_after: Option<std::time::Duration>,
+        /// Start of the response body
+        body: String,
+    },
+    /// API response error: the raw code and message, plus the server's request id.
+    /// Use [`TushareError::kind`] to tell throttling from permission or parameter errors.
     ApiError {
         code: i32,
         message: String,
+        request_id: String,
     },
     /// JSON serialization/deserialization error
     SerializationError(serde_json::Error),
-    /// Network timeout error
+    /// Network timeout error, or the call's deadline passed
     TimeoutError,
+    /// The call was cancelled through its cancellation token
+    Cancelled,
+    /// A daily quota of the rate limiter is used up
+    QuotaExhausted {
+        api_name: String,
+        /// Time until the quota resets (midnight Beijing time)
+        resets_in: std::time::Duration,
+    },
+    /// The circuit breaker of the API is open; the call was not sent
+    CircuitOpen {
+        api_name: String,
+        /// Time until a probe call is allowed
+        retry_in: std::time::Duration,
+    },
+    /// The response has as many rows as the API returns per call, so rows may be missing.
+    /// Raised with [`TruncationPolicy::Error`](crate::truncation::TruncationPolicy::Error).
+    PossiblyTruncated {
+        api_name: String,
+        rows: usize,
+    },
+    /// The request does not match its API's schema; it was not sent.
+    /// Raised by [`TushareRequest::validate`](crate::TushareRequest::validate) and strict clients.
+    InvalidRequest {
+        api_name: String,
+        problems: Vec<String>,
+    },
     /// Invalid API Token
     InvalidToken,
+    /// Error of a request shared by identical in-flight calls or a merged batch (see
+    /// [`single_flight`](crate::single_flight)). Behaves like the wrapped error; match on
+    /// [`TushareError::kind`] rather than on the variant
+    Shared(Arc<TushareError>),
     /// Data parsing error
     ParseError(String),
     /// Other errors
@@ -28,23 +131,96 @@ impl fmt::Display for TushareError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TushareError::HttpError(err) => write!(f, "HTTP request error: {err}"),
-            TushareError::ApiError { code, message } => {
-                write!(f, "API error (code: {code}): {message}")
+            TushareError::HttpStatus { status, body, .. } => write!(f, "HTTP status {status}: {body}"),
+            TushareError::ApiError { code, message, request_id } => {
+                write!(f, "API error (code: {code}, request_id: {request_id}): {message}")
             }
             TushareError::SerializationError(err) => write!(f, "Serialization error: {err}"),
             TushareError::TimeoutError => write!(f, "Request timeout"),
+            TushareError::Cancelled => write!(f, "Request cancelled"),
+            TushareError::QuotaExhausted { api_name, resets_in } => {
+                write!(f, "Daily quota exhausted for API {api_name}, resets in {}s", resets_in.as_secs())
+            }
+            TushareError::CircuitOpen { api_name, retry_in } => {
+                write!(f, "Circuit open for API {api_name}, retry in {}ms", retry_in.as_millis())
+            }
+            TushareError::PossiblyTruncated { api_name, rows } => {
+                write!(f, "Response of API {api_name} possibly truncated at {rows} rows")
+            }
+            TushareError::InvalidRequest { api_name, problems } => {
+                write!(f, "Invalid request for API {api_name}: {}", problems.join("; "))
+            }
             TushareError::InvalidToken => write!(f, "Invalid API Token"),
+            TushareError::Shared(err) => err.fmt(f),
             TushareError::ParseError(msg) => write!(f, "Parse error: {msg}"),
             TushareError::Other(msg) => write!(f, "Other error: {msg}"),
         }
     }
 }
 
+impl TushareError {
+    /// Broad category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            TushareError::HttpError(_) => ErrorKind::Network,
+            TushareError::HttpStatus { status: 429, .. } => ErrorKind::RateLimited,
+            TushareError::HttpStatus { status: 500..=599, .. } => ErrorKind::ServerError,
+            TushareError::HttpStatus { .. } => ErrorKind::Other,
+            TushareError::ApiError { code, message, .. } => ErrorKind::from_api_code(*code, message),
+            TushareError::SerializationError(_) => ErrorKind::Serialization,
+            TushareError::TimeoutError => ErrorKind::Timeout,
+            TushareError::Cancelled => ErrorKind::Cancelled,
+            TushareError::QuotaExhausted { .. } => ErrorKind::QuotaExhausted,
+            TushareError::CircuitOpen { .. } => ErrorKind::CircuitOpen,
+            TushareError::PossiblyTruncated { .. } => ErrorKind::PossiblyTruncated,
+            TushareError::InvalidRequest { .. } => ErrorKind::InvalidParameter,
+            TushareError::InvalidToken => ErrorKind::InvalidToken,
+            TushareError::Shared(err) => err.kind(),
+            TushareError::ParseError(_) => ErrorKind::Parse,
+            TushareError::Other(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the same call may succeed if tried again later: network failures,
+    /// timeouts, rate limiting and server errors (including HTTP 429 and 5xx)
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Network | ErrorKind::Timeout | ErrorKind::RateLimited | ErrorKind::ServerError
+        )
+    }
+
+    /// Tushare response code, for API errors
+    pub fn api_code(&self) -> Option<i32> {
+        match self.root() {
+            TushareError::ApiError { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// Server-side request id, for API errors
+    pub fn api_request_id(&self) -> Option<&str> {
+        match self.root() {
+            TushareError::ApiError { request_id, .. } => Some(request_id),
+            _ => None,
+        }
+    }
+
+    /// The error itself, or the original error behind a [`TushareError::Shared`]
+    pub(crate) fn root(&self) -> &TushareError {
+        match self {
+            TushareError::Shared(err) => err.root(),
+            err => err,
+        }
+    }
+}
+
 impl StdError for TushareError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
             TushareError::HttpError(err) => Some(err),
             TushareError::SerializationError(err) => Some(err),
+            TushareError::Shared(err) => err.source(),
             _ => None,
         }
     }
@@ -74,3 +250,38 @@ impl From<Infallible> for TushareError {
 
 /// Tushare API result type
 pub type TushareResult<T> = Result<T, TushareError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(code: i32, message: &str) -> TushareError {
+        TushareError::ApiError {
+            code,
+            message: message.to_string(),
+            request_id: "r1".to_string(),
+        }
+    }
+
+    #[test]
+    fn classifies_api_codes() {
+        let throttled = api_error(40203, "抱歉，您每分钟最多访问该接口500次");
+        assert_eq!(throttled.kind(), ErrorKind::RateLimited);
+        assert!(throttled.is_retryable());
+
+        let denied = api_error(40203, "抱歉，您没有访问该接口的权限");
+        assert_eq!(denied.kind(), ErrorKind::PermissionDenied);
+        assert!(!denied.is_retryable());
+
+        for capped in ["抱歉，您每小时最多访问该接口2次", "抱歉，您每天最多访问该接口100000次"] {
+            let capped = api_error(40203, capped);
+            assert_eq!(capped.kind(), ErrorKind::QuotaExhausted);
+            assert!(!capped.is_retryable());
+        }
+
+        assert_eq!(api_error(40101, "token不对").kind(), ErrorKind::InvalidToken);
+        assert_eq!(api_error(40001, "参数错误").kind(), ErrorKind::InvalidParameter);
+        assert_eq!(api_error(50101, "系统内部错误").kind(), ErrorKind::OtherApi);
+        assert_eq!(denied.api_request_id(), Some("r1"));
+    }
+}
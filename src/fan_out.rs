@@ -0,0 +1,191 @@
+//! Run one request template over many parameter sets
+//!
+//! Most batch jobs send the same request for thousands of `ts_code`s.
+//! [`TushareClientEx::fan_out`](crate::TushareClientEx::fan_out) takes a template request
+//! and an iterator of parameter overrides, runs the resulting calls with bounded concurrency
+//! (through the client's rate limiting, retries and circuit breaker), and yields a
+//! [`FanOutItem`] per override, in input order. A failing call only fails its own item; once
+//! the stream is drained, [`FanOut::summary`] counts successes, failures and retries.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use tushare_api::{Api, TushareClientEx, TushareRequest};
+//!
+//! # async fn example(client: TushareClientEx, codes: Vec<String>) {
+//! let template = TushareRequest::new(Api::Daily, [("start_date", "20240101")], ["ts_code", "close"]);
+//! let overrides = codes.into_iter().map(|code| [("ts_code", code)]);
+//!
+//! let mut batch = client.fan_out(&template, overrides, 8);
+//! while let Some(item) = batch.next().await {
+//!     if let Err(err) = &item.result {
+//!         eprintln!("{:?} failed: {err}", item.key);
+//!     }
+//! }
+//! let summary = batch.summary();
+//! println!("{} ok, {} failed, {} retries", summary.succeeded, summary.failed, summary.retries);
+//! # }
+//! ```
+
+use crate::call_options::CallOptions;
+use crate::client_ex::TushareClientEx;
+use crate::error::{ErrorKind, TushareResult};
+use crate::types::{TushareRequest, TushareResponse};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
+use std::task::{Context, Poll};
+
+/// Outcome of the call made for one parameter override
+#[derive(Debug)]
+pub struct FanOutItem<P> {
+    /// The override the call was made with
+    pub key: P,
+    /// Response or error of the call
+    pub result: TushareResult<TushareResponse>,
+    /// Retries the call needed
+    pub retries: usize,
+}
+
+/// Counts over the items yielded so far
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FanOutSummary {
+    /// Calls that succeeded
+    pub succeeded: usize,
+    /// Calls that failed
+    pub failed: usize,
+    /// Retries over all calls
+    pub retries: usize,
+    /// Failed calls by error kind
+    pub failures_by_kind: HashMap<ErrorKind, usize>,
+}
+
+impl FanOutSummary {
+    /// Number of calls finished
+    pub fn total(&self) -> usize {
+        self.succeeded + self.failed
+    }
+
+    fn record<P>(&mut self, item: &FanOutItem<P>) {
+        self.retries += item.retries;
+        match &item.result {
+            Ok(_) => self.succeeded += 1,
+            Err(err) => {
+                self.failed += 1;
+                *self.failures_by_kind.entry(err.kind()).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Stream of [`FanOutItem`]s that keeps a running [`FanOutSummary`]
+pub struct FanOut<'a, P> {
+    items: BoxStream<'a, FanOutItem<P>>,
+    summary: FanOutSummary,
+}
+
+impl<'a, P> FanOut<'a, P> {
+    pub(crate) fn new<I, K, V>(client: &'a TushareClientEx, template: TushareRequest, overrides: I, concurrency: usize) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        I::IntoIter: Send + 'a,
+        P: IntoIterator<Item = (K, V)> + Clone + Send + 'a,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let items = stream::iter(overrides)
+            .map(move |key: P| {
+                let mut request = template.clone();
+                for (name, value) in key.clone() {
+                    request.params.insert(name.into(), value.into());
+                }
+                async move {
+                    let retries = AtomicUsize::new(0);
+                    let result = client
+                        .call_request_counting_retries(request, CallOptions::default(), &retries)
+                        .await;
+                    FanOutItem {
+                        key,
+                        result,
+                        retries: retries.into_inner(),
+                    }
+                }
+            })
+            .buffered(concurrency.max(1))
+            .boxed();
+        Self {
+            items,
+            summary: FanOutSummary::default(),
+        }
+    }
+
+    /// Counts over the items yielded so far; complete once the stream is drained
+    pub fn summary(&self) -> &FanOutSummary {
+        &self.summary
+    }
+
+    /// Drain the stream, returning every item and the final summary
+    pub async fn collect(mut self) -> (Vec<FanOutItem<P>>, FanOutSummary) {
+        let mut items = Vec::new();
+        while let Some(item) = self.next().await {
+            items.push(item);
+        }
+        (items, self.summary)
+    }
+}
+
+impl<P> Stream for FanOut<'_, P> {
+    type Item = FanOutItem<P>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let poll = this.items.poll_next_unpin(cx);
+        if let Poll::Ready(Some(item)) = &poll {
+            this.summary.record(item);
+        }
+        poll
+    }
+}
+
+impl<P> std::fmt::Debug for FanOut<'_, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FanOut").field("summary", &self.summary).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::fixtures::{error_response, rows_response};
+    use crate::transport::InMemoryTransport;
+    use crate::{Api, ErrorKind, TushareClient, TushareClientEx, TushareRequest};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn yields_every_override_in_order_despite_failures() {
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(InMemoryTransport::new(|request| {
+                if request.body.contains("000002.SZ") {
+                    return Ok(error_response(40001, "参数错误"));
+                }
+                Ok(rows_response(&["close"], vec![json!([10.5])]))
+            }))
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let template = TushareRequest::new(Api::Daily, [("trade_date", "20240102")], ["close"]);
+        let codes = ["000001.SZ", "000002.SZ", "000003.SZ"];
+
+        let (items, summary) = client
+            .fan_out(&template, codes.iter().map(|code| [("ts_code", *code)]), 2)
+            .collect()
+            .await;
+        let keys: Vec<&str> = items.iter().map(|item| item.key[0].1).collect();
+        assert_eq!(keys, codes);
+        assert!(items[1].result.is_err());
+        assert_eq!((summary.succeeded, summary.failed, summary.retries), (2, 1, 0));
+        assert_eq!(summary.failures_by_kind.get(&ErrorKind::InvalidParameter), Some(&1));
+    }
+}
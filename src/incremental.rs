@@ -0,0 +1,362 @@
+//! Incremental sync: fetch only rows newer than what is already stored
+//!
+//! A daily job usually only needs the bars published since its last run. [`IncrementalSync`]
+//! takes the keys to sync (e.g. `ts_code`s) and a lookup returning the last date stored for
+//! each key. Using the trading calendar, it works out the first trading day after each stored
+//! date, skips keys that are already up to date (a weekend or holiday is not missing data),
+//! groups the remaining keys by start date into multi-key requests, and returns only the rows
+//! newer than each key's stored date.
+//!
+//! Keys without stored data are fetched from [`IncrementalSync::with_initial_start`], or
+//! from the start of the API's history. A first sync may therefore hit the API's row cap;
+//! see [`TushareClientEx::with_auto_split`](crate::TushareClientEx::with_auto_split).
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::collections::HashMap;
+//! use tushare_api::{Api, DeriveFromTushareData, TushareClientEx, TushareResult};
+//! use tushare_api::incremental::IncrementalSync;
+//!
+//! #[derive(Debug, Clone, DeriveFromTushareData)]
+//! struct Daily {
+//!     ts_code: String,
+//!     trade_date: String,
+//!     close: f64,
+//! }
+//!
+//! # async fn example(client: TushareClientEx, stored: HashMap<String, String>) -> TushareResult<()> {
+//! let new_rows = IncrementalSync::new(Api::Daily)
+//!     .with_fields(["ts_code", "trade_date", "close"])
+//!     .with_initial_start("20200101")
+//!     .run::<Daily, _, _>(&client, stored.keys().cloned(), |code| stored.get(code).cloned())
+//!     .await?;
+//! println!("{} new rows", new_rows.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::Api;
+use crate::chunking::{self, END_DATE_PARAM, START_DATE_PARAM};
+use crate::client_ex::TushareClientEx;
+use crate::dates;
+use crate::error::{TushareError, TushareResult};
+use crate::traits::FromTushareData;
+use crate::types::{TushareEntityList, TushareRequest};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use std::collections::{BTreeMap, HashMap};
+
+/// Builds and runs the requests of an incremental sync
+#[derive(Debug, Clone)]
+pub struct IncrementalSync {
+    api: Api,
+    key_field: String,
+    date_field: String,
+    fields: Vec<String>,
+    params: HashMap<String, String>,
+    exchange: String,
+    initial_start: Option<String>,
+    end_date: Option<String>,
+    max_keys: usize,
+    concurrency: usize,
+}
+
+impl IncrementalSync {
+    /// Sync of `api`, keyed by `ts_code` and dated by `trade_date`
+    pub fn new(api: Api) -> Self {
+        Self {
+            api,
+            key_field: "ts_code".to_string(),
+            date_field: "trade_date".to_string(),
+            fields: Vec::new(),
+            params: HashMap::new(),
+            exchange: "SSE".to_string(),
+            initial_start: None,
+            end_date: None,
+            max_keys: 50,
+            concurrency: 4,
+        }
+    }
+
+    /// Set the parameter and response field holding the key (default: `ts_code`)
+    pub fn with_key_field<S: Into<String>>(mut self, field: S) -> Self {
+        self.key_field = field.into();
+        self
+    }
+
+    /// Set the response field holding the row date (default: `trade_date`)
+    pub fn with_date_field<S: Into<String>>(mut self, field: S) -> Self {
+        self.date_field = field.into();
+        self
+    }
+
+    /// Request only `fields`; the key and date fields are added if missing
+    pub fn with_fields<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a parameter sent with every request
+    pub fn with_param<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the exchange of the trading calendar (default: `SSE`)
+    pub fn with_exchange<S: Into<String>>(mut self, exchange: S) -> Self {
+        self.exchange = exchange.into();
+        self
+    }
+
+    /// Fetch keys without stored data from `date` (default: the whole history)
+    pub fn with_initial_start<S: Into<String>>(mut self, date: S) -> Self {
+        self.initial_start = Some(date.into());
+        self
+    }
+
+    /// Sync up to `date` inclusive (default: today, Beijing time)
+    pub fn with_end_date<S: Into<String>>(mut self, date: S) -> Self {
+        self.end_date = Some(date.into());
+        self
+    }
+
+    /// Put at most `max_keys` keys in one request (default: 50)
+    pub fn with_max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = max_keys.max(1);
+        self
+    }
+
+    /// Run at most `concurrency` requests at once (default: 4)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The requests needed to bring `keys` up to date, given the last stored date of each
+    /// key (`None` if nothing is stored). Reads the trading calendar.
+    pub async fn plan<I, F>(&self, client: &TushareClientEx, keys: I, last_stored: F) -> TushareResult<Vec<TushareRequest>>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let stored = lookup(keys, last_stored);
+        self.requests(client, &stored).await
+    }
+
+    /// Fetch the rows of `keys` newer than their last stored date, ordered by date
+    pub async fn run<T, I, F>(&self, client: &TushareClientEx, keys: I, last_stored: F) -> TushareResult<TushareEntityList<T>>
+    where
+        T: FromTushareData,
+        I: IntoIterator,
+        I::Item: Into<String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let stored = lookup(keys, last_stored);
+        let requests = self.requests(client, &stored).await?;
+        let pages: Vec<_> = stream::iter(requests)
+            .map(|request| async move { client.call_api(&request).await })
+            .buffered(self.concurrency)
+            .try_collect()
+            .await?;
+
+        let merged = chunking::merge(pages.into_iter().filter_map(|page| page.data), Some(&self.date_field));
+        if merged.items.is_empty() {
+            return Ok(TushareEntityList::new(Vec::new(), false, 0));
+        }
+        let column = |name: &str| {
+            merged
+                .fields
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| TushareError::ParseError(format!("Response has no {name} field")))
+        };
+        let (key_column, date_column) = (column(&self.key_field)?, column(&self.date_field)?);
+
+        let mut items = Vec::new();
+        for row in &merged.items {
+            let key = row.get(key_column).and_then(|value| value.as_str()).unwrap_or_default();
+            let date = row.get(date_column).and_then(|value| value.as_str()).unwrap_or_default();
+            let is_new = match stored.get(key) {
+                Some(Some(last)) => date > last.as_str(),
+                _ => true,
+            };
+            if is_new {
+                items.push(T::from_row(&merged.fields, row)?);
+            }
+        }
+        let count = items.len() as i64;
+        Ok(TushareEntityList::new(items, false, count))
+    }
+
+    async fn requests(
+        &self,
+        client: &TushareClientEx,
+        stored: &BTreeMap<String, Option<String>>,
+    ) -> TushareResult<Vec<TushareRequest>> {
+        let end_date = self.end_date.clone().unwrap_or_else(dates::today);
+        for last in stored.values().flatten() {
+            dates::parse_date(last)?;
+        }
+
+        // Trading days after the oldest stored date
+        let trade_days = match stored.values().flatten().min() {
+            Some(oldest) => {
+                let from = dates::add_days(oldest, 1)?;
+                if from <= end_date {
+                    dates::trade_days(client, &self.exchange, &from, &end_date).await?
+                } else {
+                    Vec::new()
+                }
+            }
+            None => Vec::new(),
+        };
+
+        let mut by_start: BTreeMap<Option<String>, Vec<&str>> = BTreeMap::new();
+        for (key, last) in stored {
+            let start = match last {
+                Some(last) => match trade_days.iter().find(|day| *day > last) {
+                    Some(day) => Some(day.clone()),
+                    // No trading since the last stored date
+                    None => continue,
+                },
+                None => self.initial_start.clone(),
+            };
+            by_start.entry(start).or_default().push(key);
+        }
+
+        let mut fields = self.fields.clone();
+        if !fields.is_empty() {
+            for field in [&self.key_field, &self.date_field] {
+                if !fields.contains(field) {
+                    fields.push(field.clone());
+                }
+            }
+        }
+        let mut requests = Vec::new();
+        for (start, keys) in by_start {
+            for keys in keys.chunks(self.max_keys) {
+                let mut params = self.params.clone();
+                params.insert(self.key_field.clone(), keys.join(","));
+                if let Some(start) = &start {
+                    params.insert(START_DATE_PARAM.to_string(), start.clone());
+                }
+                params.insert(END_DATE_PARAM.to_string(), end_date.clone());
+                requests.push(TushareRequest {
+                    api_name: self.api.clone(),
+                    params,
+                    fields: fields.clone(),
+                });
+            }
+        }
+        Ok(requests)
+    }
+}
+
+/// The last stored date of each distinct key
+fn lookup<I, F>(keys: I, last_stored: F) -> BTreeMap<String, Option<String>>
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+    F: Fn(&str) -> Option<String>,
+{
+    keys.into_iter()
+        .map(|key| {
+            let key = key.into();
+            let last = last_stored(&key);
+            (key, last)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::rows_response;
+    use crate::transport::InMemoryTransport;
+    use crate::TushareClient;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    const TRADE_DAYS: [&str; 5] = ["20240102", "20240103", "20240104", "20240105", "20240108"];
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Row(String, String);
+
+    impl FromTushareData for Row {
+        fn from_row(fields: &[String], values: &[serde_json::Value]) -> TushareResult<Self> {
+            Ok(Row(
+                crate::utils::get_string_field(fields, values, "ts_code")?,
+                crate::utils::get_string_field(fields, values, "trade_date")?,
+            ))
+        }
+    }
+
+    /// Serves the calendar, and daily rows from 20240104 on whatever the start date
+    fn market() -> Arc<InMemoryTransport> {
+        Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let (fields, items): (_, Vec<_>) = if body["api_name"] == "trade_cal" {
+                (vec!["cal_date"], TRADE_DAYS.iter().map(|day| json!([day])).collect())
+            } else {
+                let codes = body["params"]["ts_code"].as_str().unwrap().split(',');
+                let items = codes
+                    .flat_map(|code| TRADE_DAYS[2..].iter().map(move |day| json!([code, day])))
+                    .collect();
+                (vec!["ts_code", "trade_date"], items)
+            };
+            Ok(rows_response(&fields, items))
+        }))
+    }
+
+    #[tokio::test]
+    async fn fetches_only_rows_after_the_last_stored_trade_date() {
+        let transport = market();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client);
+        let stored = HashMap::from([
+            ("000001.SZ", "20240105"),
+            ("000002.SZ", "20240105"),
+            ("000003.SZ", "20240108"),
+        ]);
+        let keys = ["000001.SZ", "000002.SZ", "000003.SZ", "000004.SZ"];
+        let sync = IncrementalSync::new(Api::Daily)
+            .with_fields(["close"])
+            .with_initial_start("20240104")
+            .with_end_date("20240108");
+
+        let rows = sync
+            .run::<Row, _, _>(&client, keys, |code| stored.get(code).map(|date| date.to_string()))
+            .await
+            .unwrap();
+        let rows: Vec<(&str, &str)> = rows.iter().map(|row| (row.0.as_str(), row.1.as_str())).collect();
+        assert_eq!(
+            rows,
+            [
+                ("000004.SZ", "20240104"),
+                ("000004.SZ", "20240105"),
+                ("000004.SZ", "20240108"),
+                ("000001.SZ", "20240108"),
+                ("000002.SZ", "20240108"),
+            ]
+        );
+
+        // The calendar, then one request per start date; 000003.SZ is up to date
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        let body: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body["params"]["ts_code"], "000004.SZ");
+        assert_eq!(body["params"]["start_date"], "20240104");
+        let body: serde_json::Value = serde_json::from_str(&requests[2].body).unwrap();
+        assert_eq!(body["params"]["ts_code"], "000001.SZ,000002.SZ");
+        assert_eq!(body["params"]["start_date"], "20240108");
+    }
+}
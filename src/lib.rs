@@ -11,7 +11,9 @@
 //! - **Error Handling**: Comprehensive error types and handling
 //! - **Logging Support**: Built-in logging with configurable levels
 //! - **Async Support**: Full async/await support with tokio
+//! - **Blocking Client**: Optional synchronous client behind the `blocking` feature
 //! - **Flexible Configuration**: Customizable HTTP client settings
+//! - **Pluggable Transport**: Swap the `reqwest` transport for in-memory or custom ones
 //! - **Environment Integration**: Automatic token loading from environment variables
 //! - **Automatic Conversion**: Derive macros for automatic struct conversion from API responses
 //!
@@ -55,11 +57,38 @@
 //! }
 //! ```
 
+// Lets the derive macros' `tushare_api::` paths resolve in the crate's own tests
+#[cfg(test)]
+extern crate self as tushare_api;
+
 pub mod error;
 pub mod api;
 pub mod types;
 pub mod client;
 pub mod client_ex;
+pub mod transport;
+pub mod cassette;
+pub mod cache;
+pub mod single_flight;
+pub mod pagination;
+pub mod chunking;
+pub mod fan_out;
+pub mod batching;
+pub mod truncation;
+pub mod schema;
+pub mod bulk;
+pub mod incremental;
+pub mod dates;
+pub mod concurrency;
+pub mod circuit_breaker;
+pub mod retry;
+pub mod rate_limit;
+pub mod call_options;
+pub mod middleware;
+pub mod secret;
+pub mod token_pool;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod logging;
 pub mod traits;
 pub mod utils;
@@ -68,11 +97,14 @@ pub mod third_party_types;
 pub mod custom_date_format;
 
 // Re-export main types for convenience
-pub use error::{TushareError, TushareResult};
-pub use api::Api;
-pub use types::{TushareRequest, TushareResponse, TushareData, TushareEntityList};
-pub use client::{TushareClient, HttpClientConfig};
+pub use error::{ErrorKind, TushareError, TushareResult};
+pub use api::{Api, ApiCategory, ApiInfo};
+pub use types::{TushareRequest, TushareResponse, TushareData, TushareEntityList, RequestKey};
+pub use secret::SecretToken;
+pub use client::{TushareClient, HttpClientConfig, ProxyConfig, TlsBackend};
 pub use client_ex::TushareClientEx;
+pub use call_options::CallOptions;
+pub use transport::{Transport, TransportRequest, TransportResponse, ReqwestTransport, InMemoryTransport};
 pub use logging::{LogConfig, LogLevel, Logger};
 pub use traits::{FromTushareData, FromTushareValue, FromOptionalTushareValue};
 pub use utils::response_to_vec;
@@ -4,7 +4,7 @@ use log::{info, debug, error, trace, warn};
 use tracing::{info as tracing_info, debug as tracing_debug, error as tracing_error, trace as tracing_trace, warn as tracing_warn};
 
 /// Log level configuration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum LogLevel {
     /// Disable logging
     Off,
@@ -13,6 +13,7 @@ pub enum LogLevel {
     /// Log errors and warnings
     Warn,
     /// Log basic information (default)
+    #[default]
     Info,
     /// Log detailed debug information
     Debug,
@@ -20,12 +21,6 @@ pub enum LogLevel {
     Trace,
 }
 
-impl Default for LogLevel {
-    fn default() -> Self {
-        LogLevel::Info
-    }
-}
-
 /// Log configuration
 #[derive(Debug, Clone)]
 pub struct LogConfig {
@@ -157,6 +152,73 @@ impl Logger {
         );
     }
 
+    /// Log the label of the token serving a request
+    pub fn log_token_selected(&self, request_id: &str, label: &str) {
+        let request_id = request_id.to_string();
+        let label = label.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Using token: {}", request_id, label),
+            None,
+        );
+    }
+
+    /// Log a wait because every usable token spent its per-minute budget
+    pub fn log_token_budget_wait(&self, request_id: &str, wait: std::time::Duration) {
+        let request_id = request_id.to_string();
+        self.log_safe(
+            LogLevel::Info,
+            move || format!("[{}] All token budgets exhausted, waiting {:?}", request_id, wait),
+            None,
+        );
+    }
+
+    /// Log a token taken out of rotation after an auth or quota error
+    pub fn log_token_quarantined(&self, request_id: &str, label: &str, duration: std::time::Duration, error: &str) {
+        let request_id = request_id.to_string();
+        let label = label.to_string();
+        let error = error.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!(
+                "[{}] Token {} quarantined for {:?}, error: {}",
+                request_id, label, duration, error
+            ),
+            None,
+        );
+    }
+
+    /// Log a token left out of one API after a permission error
+    pub fn log_token_denied(&self, request_id: &str, label: &str, api_name: &str, duration: std::time::Duration, error: &str) {
+        let request_id = request_id.to_string();
+        let label = label.to_string();
+        let api_name = api_name.to_string();
+        let error = error.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!(
+                "[{}] Token {} left out of API {} for {:?}, error: {}",
+                request_id, label, api_name, duration, error
+            ),
+            None,
+        );
+    }
+
+    /// Log a token resting until its per-minute window frees up after a rate-limit reply
+    pub fn log_token_throttled(&self, request_id: &str, label: &str, duration: std::time::Duration, error: &str) {
+        let request_id = request_id.to_string();
+        let label = label.to_string();
+        let error = error.to_string();
+        self.log_safe(
+            LogLevel::Info,
+            move || format!(
+                "[{}] Token {} throttled by Tushare, resting {:?}, error: {}",
+                request_id, label, duration, error
+            ),
+            None,
+        );
+    }
+
     /// Log HTTP request sending
     pub fn log_http_request(&self, request_id: &str) {
         let request_id = request_id.to_string();
@@ -167,6 +229,36 @@ impl Logger {
         );
     }
 
+    /// Log an endpoint that failed at the network level and is being skipped
+    pub fn log_endpoint_failover(&self, request_id: &str, endpoint: &str, remaining: usize, error: &str) {
+        let request_id = request_id.to_string();
+        let endpoint = endpoint.to_string();
+        let error = error.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!(
+                "[{}] Endpoint {} failed, failing over ({} endpoint(s) left), error: {}",
+                request_id, endpoint, remaining, error
+            ),
+            None,
+        );
+    }
+
+    /// Log the endpoint that served the request
+    pub fn log_endpoint_served(&self, request_id: &str, endpoint: &str, failover_count: usize) {
+        let request_id = request_id.to_string();
+        let endpoint = endpoint.to_string();
+        let level = if failover_count > 0 { LogLevel::Info } else { LogLevel::Debug };
+        self.log_safe(
+            level,
+            move || format!(
+                "[{}] Request served by endpoint {} (after {} failover(s))",
+                request_id, endpoint, failover_count
+            ),
+            None,
+        );
+    }
+
     /// Log HTTP request failure
     pub fn log_http_error(&self, request_id: &str, elapsed: std::time::Duration, error: &str) {
         let request_id = request_id.to_string();
@@ -254,6 +346,170 @@ impl Logger {
         );
     }
 
+    /// Log a response served from the cache
+    pub fn log_cache_hit(&self, request_id: &str, api_name: &str, tier: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        let tier = tier.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Cache hit ({}) for API: {}", request_id, tier, api_name),
+            None,
+        );
+    }
+
+    /// Log a cache lookup that found no fresh response
+    pub fn log_cache_miss(&self, request_id: &str, api_name: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Cache miss for API: {}", request_id, api_name),
+            None,
+        );
+    }
+
+    pub fn log_single_flight_join(&self, request_id: &str, api_name: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Shared in-flight request for API: {}", request_id, api_name),
+            None,
+        );
+    }
+
+    /// Log a middleware answering or rejecting a call before it is sent
+    pub fn log_middleware_short_circuit(&self, request_id: &str, middleware: &str, rejected: bool) {
+        let request_id = request_id.to_string();
+        let middleware = middleware.to_string();
+        let outcome = if rejected { "rejected" } else { "answered" };
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Call {} by middleware: {}", request_id, outcome, middleware),
+            None,
+        );
+    }
+
+    /// Log a circuit breaker state change
+    pub fn log_circuit_transition(&self, request_id: &str, api_name: &str, from: &str, to: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        let from = from.to_string();
+        let to = to.to_string();
+        let level = if to == "open" { LogLevel::Warn } else { LogLevel::Info };
+        self.log_safe(
+            level,
+            move || format!("[{}] Circuit for API {} changed: {} -> {}", request_id, api_name, from, to),
+            None,
+        );
+    }
+
+    /// Log a call that waited for a concurrency slot
+    pub fn log_queue_wait(&self, request_id: &str, api_name: &str, waited: std::time::Duration) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Queued for concurrency slot, API: {}, waited {:?}", request_id, api_name, waited),
+            None,
+        );
+    }
+
+    /// Log a call delayed by the rate limiter
+    pub fn log_rate_limit_wait(&self, request_id: &str, api_name: &str, waited: std::time::Duration) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Rate limited API: {}, waited {:?}", request_id, api_name, waited),
+            None,
+        );
+    }
+
+    /// Log a chunked-fetch window that hit the row cap and is split again
+    pub fn log_window_split(&self, request_id: &str, api_name: &str, start_date: &str, end_date: &str, rows: usize) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        let window = format!("{}..={}", start_date, end_date);
+        self.log_safe(
+            LogLevel::Info,
+            move || format!("[{}] API {} returned {} rows for {}, splitting the window", request_id, api_name, rows, window),
+            None,
+        );
+    }
+
+    /// Log single-code requests merged into one multi-code call
+    pub fn log_batch_merged(&self, request_id: &str, api_name: &str, codes: usize) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Debug,
+            move || format!("[{}] Batched {} codes into one call of API: {}", request_id, codes, api_name),
+            None,
+        );
+    }
+
+    /// Log a merged call whose reply reached the row cap, so its callers send their own requests
+    pub fn log_batch_at_row_cap(&self, request_id: &str, api_name: &str, codes: usize) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || {
+                format!(
+                    "[{}] Batched call of API {} reached the row cap; sending its {} codes one by one",
+                    request_id, api_name, codes
+                )
+            },
+            None,
+        );
+    }
+
+    /// Log a response that reached its API's row cap
+    pub fn log_possibly_truncated(&self, request_id: &str, api_name: &str, rows: usize) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!("[{}] API {} returned {} rows, its row cap; the response may be truncated", request_id, api_name, rows),
+            None,
+        );
+    }
+
+    /// Log a possibly truncated request re-issued in two halves
+    pub fn log_truncation_split(&self, request_id: &str, api_name: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Info,
+            move || format!("[{}] Splitting possibly truncated request of API: {}", request_id, api_name),
+            None,
+        );
+    }
+
+    /// Log a strict client sending a request it has no schema to validate against
+    pub fn log_unvalidated_request(&self, request_id: &str, api_name: &str) {
+        let request_id = request_id.to_string();
+        let api_name = api_name.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!("[{}] No schema for API {}; sending the request unvalidated", request_id, api_name),
+            None,
+        );
+    }
+
+    /// Log a call aborted by cancellation or its deadline
+    pub fn log_call_aborted(&self, request_id: &str, reason: &str) {
+        let request_id = request_id.to_string();
+        let reason = reason.to_string();
+        self.log_safe(
+            LogLevel::Warn,
+            move || format!("[{}] Call aborted: {}", request_id, reason),
+            None,
+        );
+    }
+
     /// Log API call success
     pub fn log_api_success(&self, request_id: &str, elapsed: std::time::Duration, data_count: usize) {
         let request_id = request_id.to_string();
@@ -0,0 +1,228 @@
+//! Request/response middleware for [`TushareClient`](crate::TushareClient)
+//!
+//! Middlewares are registered on the builder with
+//! [`with_middleware`](crate::client::TushareClientBuilder::with_middleware) and run in
+//! registration order before each request is sent, then in reverse order once the result
+//! is known. A middleware can edit the request (add default params, rewrite the API name),
+//! add HTTP headers, reject the call with an error or answer it with a synthetic response
+//! without touching the network.
+//!
+//! Middlewares run on every attempt made by [`TushareClientEx`](crate::TushareClientEx) and
+//! wrap the response cache, so cache lookups use the request as edited by the middlewares.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::middleware::{Middleware, RequestContext};
+//! use tushare_api::transport::BoxFuture;
+//! use tushare_api::{TushareClient, TushareRequest, TushareResponse, TushareResult};
+//!
+//! /// Adds `exchange=SSE` unless the caller set an exchange, and tags every request
+//! #[derive(Debug)]
+//! struct DefaultExchange;
+//!
+//! impl Middleware for DefaultExchange {
+//!     fn before<'a>(
+//!         &'a self,
+//!         context: &'a mut RequestContext,
+//!         request: &'a mut TushareRequest,
+//!     ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+//!         Box::pin(async move {
+//!             request.params.entry("exchange".to_string()).or_insert_with(|| "SSE".to_string());
+//!             context.insert_header("X-Request-Source", "batch-loader");
+//!             Ok(None)
+//!         })
+//!     }
+//! }
+//!
+//! # fn build() -> TushareResult<TushareClient> {
+//! let client = TushareClient::builder()
+//!     .with_token("your_token_here")
+//!     .with_middleware(DefaultExchange)
+//!     .build()?;
+//! # Ok(client)
+//! # }
+//! ```
+
+use crate::error::TushareResult;
+use crate::transport::BoxFuture;
+use crate::types::{TushareRequest, TushareResponse};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Per-call state shared by the middlewares of one request
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Request id used in the client's log lines
+    pub request_id: String,
+    /// Extra HTTP headers sent with the request
+    pub headers: Vec<(String, String)>,
+    /// Timeout of each HTTP attempt; `None` uses the client's configured timeout
+    pub timeout: Option<Duration>,
+}
+
+impl RequestContext {
+    /// Create a context without extra headers
+    pub fn new<S: Into<String>>(request_id: S) -> Self {
+        Self {
+            request_id: request_id.into(),
+            headers: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Set an HTTP header, replacing an existing header of the same name
+    pub fn insert_header<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        let name = name.into();
+        let value = value.into();
+        match self
+            .headers
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(&name))
+        {
+            Some(header) => header.1 = value,
+            None => self.headers.push((name, value)),
+        }
+    }
+}
+
+/// Hook run around every request sent by the client
+///
+/// Both methods have no-op defaults, so a middleware only implements what it needs.
+pub trait Middleware: Send + Sync + fmt::Debug {
+    /// Name used in log lines
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Called before the request is sent.
+    ///
+    /// Return `Ok(Some(response))` to answer the call without sending it, or an error to
+    /// reject it. In both cases the remaining middlewares' `before` hooks are skipped.
+    fn before<'a>(
+        &'a self,
+        context: &'a mut RequestContext,
+        request: &'a mut TushareRequest,
+    ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+        let _ = (context, request);
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Called with the outcome of the request; may replace it.
+    ///
+    /// Only middlewares whose `before` hook ran are called, in reverse order.
+    fn after<'a>(
+        &'a self,
+        context: &'a RequestContext,
+        request: &'a TushareRequest,
+        result: &'a mut TushareResult<TushareResponse>,
+    ) -> BoxFuture<'a, ()> {
+        let _ = (context, request, result);
+        Box::pin(async {})
+    }
+}
+
+impl<T: Middleware + ?Sized> Middleware for Arc<T> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn before<'a>(
+        &'a self,
+        context: &'a mut RequestContext,
+        request: &'a mut TushareRequest,
+    ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+        (**self).before(context, request)
+    }
+
+    fn after<'a>(
+        &'a self,
+        context: &'a RequestContext,
+        request: &'a TushareRequest,
+        result: &'a mut TushareResult<TushareResponse>,
+    ) -> BoxFuture<'a, ()> {
+        (**self).after(context, request, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TushareError;
+    use crate::transport::fixtures::rows_transport;
+    use crate::{Api, TushareClient};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct Audit {
+        seen: Mutex<Vec<String>>,
+    }
+
+    impl Middleware for Audit {
+        fn before<'a>(
+            &'a self,
+            context: &'a mut RequestContext,
+            request: &'a mut TushareRequest,
+        ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+            Box::pin(async move {
+                request.params.entry("exchange".to_string()).or_insert_with(|| "SSE".to_string());
+                context.insert_header("X-Audit", "on");
+                Ok(None)
+            })
+        }
+
+        fn after<'a>(
+            &'a self,
+            _context: &'a RequestContext,
+            request: &'a TushareRequest,
+            result: &'a mut TushareResult<TushareResponse>,
+        ) -> BoxFuture<'a, ()> {
+            let outcome = format!("{}:{}", request.api_name.name(), result.is_ok());
+            self.seen.lock().unwrap().push(outcome);
+            Box::pin(async {})
+        }
+    }
+
+    #[derive(Debug)]
+    struct BlockDaily;
+
+    impl Middleware for BlockDaily {
+        fn before<'a>(
+            &'a self,
+            _context: &'a mut RequestContext,
+            request: &'a mut TushareRequest,
+        ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+            let result = if request.api_name == Api::Daily {
+                Err(TushareError::Other("daily is blocked".to_string()))
+            } else {
+                Ok(None)
+            };
+            Box::pin(async move { result })
+        }
+    }
+
+    #[tokio::test]
+    async fn middlewares_edit_requests_and_short_circuit() {
+        let transport = Arc::new(rows_transport(&["ts_code"], vec![json!(["600000.SH"])]));
+        let audit = Arc::new(Audit::default());
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_middleware(audit.clone())
+            .with_middleware(BlockDaily)
+            .build()
+            .unwrap();
+
+        client.call_api(&TushareRequest::new(Api::StockBasic, [("list_status", "L")], ["ts_code"])).await.unwrap();
+        let err = client.call_api(&TushareRequest::new(Api::Daily, [("ts_code", "600000.SH")], ["close"])).await;
+        assert!(matches!(err, Err(TushareError::Other(_))));
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].body.contains(r#""exchange":"SSE""#));
+        assert_eq!(requests[0].headers, [("X-Audit".to_string(), "on".to_string())]);
+        assert_eq!(*audit.seen.lock().unwrap(), ["stock_basic:true", "daily:false"]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Automatic `offset`/`limit` pagination
+//!
+//! Many Tushare APIs cap the rows of one reply and report `has_more` when more are
+//! available. [`TushareClient::call_api_paginated`](crate::TushareClient::call_api_paginated)
+//! and [`TushareClientEx::call_api_paginated`](crate::TushareClientEx::call_api_paginated)
+//! return a [`Stream`] of pages: each page is requested with `limit` set to the page size and
+//! `offset` advanced by the rows received so far, until a page reports `has_more == false`
+//! (or comes back empty). Pages are fetched one at a time, only when the stream is polled, so
+//! the rate limits of the client apply to every page.
+//!
+//! The first error ends the stream. [`collect_all`] merges all pages into one
+//! [`TushareEntityList`], and [`items`] flattens the pages into a stream of rows.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use tushare_api::{Api, DeriveFromTushareData, TushareClient, TushareRequest, TushareResult, fields, params, request};
+//! use tushare_api::pagination::collect_all;
+//!
+//! #[derive(Debug, Clone, DeriveFromTushareData)]
+//! struct Daily {
+//!     ts_code: String,
+//!     trade_date: String,
+//! }
+//!
+//! # async fn example(client: TushareClient) -> TushareResult<()> {
+//! let request = request!(Api::Daily, { "trade_date" => "20240102" }, ["ts_code", "trade_date"]);
+//!
+//! // Page by page; the stream must be pinned before polling
+//! let mut pages = std::pin::pin!(client.call_api_paginated::<Daily, _>(&request, 3000));
+//! while let Some(page) = pages.next().await {
+//!     println!("{} rows", page?.len());
+//! }
+//!
+//! // Or everything at once
+//! let all = collect_all(client.call_api_paginated::<Daily, _>(&request, 3000)).await?;
+//! println!("{} of {} rows", all.len(), all.count());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{TushareError, TushareResult};
+use crate::traits::FromTushareData;
+use crate::types::{TushareEntityList, TushareRequest, TushareResponse};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use std::future::Future;
+
+/// Request parameter holding the index of the first row
+pub const OFFSET_PARAM: &str = "offset";
+/// Request parameter holding the maximum number of rows
+pub const LIMIT_PARAM: &str = "limit";
+
+/// Whether `request` asks for one page of rows (has `offset` or `limit`).
+///
+/// A full page is expected, not truncated, so such requests are left out of truncation
+/// checks and automatic splitting.
+pub(crate) fn is_paged(request: &TushareRequest) -> bool {
+    request.params.contains_key(OFFSET_PARAM) || request.params.contains_key(LIMIT_PARAM)
+}
+
+enum PageState<F> {
+    Next { request: TushareRequest, offset: usize, fetch: F },
+    Failed(TushareError),
+    Done,
+}
+
+/// Stream the pages of `request`, fetching each page with `fetch`.
+///
+/// Paging starts at the request's own `offset` parameter, if set. This is the building block
+/// of the clients' `call_api_paginated`; use it directly to page through a custom call path.
+pub fn paginate<T, F, Fut>(
+    request: TushareResult<TushareRequest>,
+    page_size: usize,
+    fetch: F,
+) -> impl Stream<Item = TushareResult<TushareEntityList<T>>>
+where
+    T: FromTushareData,
+    F: Fn(TushareRequest) -> Fut,
+    Fut: Future<Output = TushareResult<TushareResponse>>,
+{
+    let page_size = page_size.max(1);
+    let state = match request {
+        Ok(request) => {
+            let offset = request
+                .params
+                .get(OFFSET_PARAM)
+                .and_then(|offset| offset.parse().ok())
+                .unwrap_or(0);
+            PageState::Next { request, offset, fetch }
+        }
+        Err(err) => PageState::Failed(err),
+    };
+
+    stream::unfold(state, move |state| async move {
+        match state {
+            PageState::Next { request, offset, fetch } => {
+                let mut page_request = request.clone();
+                page_request.params.insert(OFFSET_PARAM.to_string(), offset.to_string());
+                page_request.params.insert(LIMIT_PARAM.to_string(), page_size.to_string());
+
+                let page = match fetch(page_request).await {
+                    Ok(response) => TushareEntityList::<T>::try_from(response),
+                    Err(err) => Err(err),
+                };
+                let next = match &page {
+                    Ok(page) if page.has_more() && !page.is_empty() => PageState::Next {
+                        request,
+                        offset: offset + page.len(),
+                        fetch,
+                    },
+                    _ => PageState::Done,
+                };
+                Some((page, next))
+            }
+            PageState::Failed(err) => Some((Err(err), PageState::Done)),
+            PageState::Done => None,
+        }
+    })
+}
+
+/// Flatten a stream of pages into a stream of rows
+pub fn items<T, S>(pages: S) -> impl Stream<Item = TushareResult<T>>
+where
+    S: Stream<Item = TushareResult<TushareEntityList<T>>>,
+{
+    pages
+        .map_ok(|page| stream::iter(page.into_items().into_iter().map(Ok)))
+        .try_flatten()
+}
+
+/// Merge all pages into one list, keeping the `count` reported by the last page.
+///
+/// Fails with the first error of the stream.
+pub async fn collect_all<T, S>(pages: S) -> TushareResult<TushareEntityList<T>>
+where
+    S: Stream<Item = TushareResult<TushareEntityList<T>>>,
+{
+    let mut pages = std::pin::pin!(pages);
+    let mut merged = TushareEntityList::new(Vec::new(), false, 0);
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        merged.has_more = page.has_more;
+        merged.count = page.count;
+        merged.items.extend(page.items);
+    }
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::paged_rows_response;
+    use crate::transport::InMemoryTransport;
+    use crate::{Api, TushareClient};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Code(String);
+
+    impl FromTushareData for Code {
+        fn from_row(fields: &[String], values: &[serde_json::Value]) -> TushareResult<Self> {
+            let index = fields.iter().position(|field| field == "ts_code").unwrap();
+            Ok(Code(values[index].as_str().unwrap_or_default().to_string()))
+        }
+    }
+
+    /// Serves 5 rows in pages of the requested `limit`
+    fn five_rows() -> Arc<InMemoryTransport> {
+        Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let offset: usize = body["params"]["offset"].as_str().unwrap().parse().unwrap();
+            let limit: usize = body["params"]["limit"].as_str().unwrap().parse().unwrap();
+            let end = (offset + limit).min(5);
+            let items: Vec<_> = (offset..end).map(|i| json!([format!("00000{i}.SZ")])).collect();
+            Ok(paged_rows_response(&["ts_code"], items, end < 5, 5))
+        }))
+    }
+
+    #[tokio::test]
+    async fn pages_until_has_more_is_false() {
+        let transport = five_rows();
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(Api::StockBasic, [("list_status", "L")], ["ts_code"]);
+
+        let all = collect_all(client.call_api_paginated::<Code, _>(&request, 2)).await.unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!((all.has_more(), all.count()), (false, 5));
+        assert_eq!(all.items[4], Code("000004.SZ".to_string()));
+        assert_eq!(transport.requests().len(), 3);
+
+        let rows: Vec<Code> = items(client.call_api_paginated::<Code, _>(&request, 4))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 5);
+    }
+}
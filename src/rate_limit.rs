@@ -0,0 +1,358 @@
+//! Token-bucket rate limiter with per-minute budgets and daily quotas
+//!
+//! Tushare limits calls as "N calls per minute" per API (depending on the account's points)
+//! and caps some APIs per day. A [`RateLimiter`] models both:
+//!
+//! - a global and/or per-API token bucket refilled at `per_minute / 60` tokens per second,
+//!   holding at most `burst` tokens;
+//! - global and/or per-API daily quotas, reset at midnight Beijing time (UTC+8), which is
+//!   when Tushare resets its counters.
+//!
+//! A call waits until every bucket that applies to it has a token; once a daily quota is used
+//! up it fails with [`TushareError::QuotaExhausted`] instead of waiting. `RateLimiter` is a
+//! cheap, cloneable handle: clones share the same budget, so several clients in one process
+//! can draw from one account's limits.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use tushare_api::{Api, TushareClient, TushareClientEx};
+//! use tushare_api::rate_limit::{RateLimit, RateLimitConfig, RateLimiter};
+//!
+//! # fn build(a: TushareClient, b: TushareClient) -> (TushareClientEx, TushareClientEx) {
+//! let limiter = RateLimiter::new(
+//!     RateLimitConfig::new()
+//!         .with_global_limit(RateLimit::per_minute(500))
+//!         .with_api_limit(Api::Daily, RateLimit::per_minute(200).with_burst(20))
+//!         .with_daily_quota(Api::FinaMainbzVip, 1_000),
+//! );
+//!
+//! let first = TushareClientEx::new(a).with_rate_limiter(limiter.clone());
+//! let second = TushareClientEx::new(b).with_rate_limiter(limiter);
+//! # (first, second)
+//! # }
+//! ```
+
+use crate::api::Api;
+use crate::error::{TushareError, TushareResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Offset of Beijing time from UTC; Tushare's daily quotas reset at Beijing midnight
+const QUOTA_DAY_OFFSET_SECS: u64 = 8 * 3600;
+const SECS_PER_DAY: u64 = 24 * 3600;
+
+/// A per-minute budget with burst capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Sustained number of calls per minute
+    pub per_minute: u32,
+    /// Maximum number of calls that can be made back to back; defaults to `per_minute`
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `calls` calls per minute, all of which may be made at once
+    pub fn per_minute(calls: u32) -> Self {
+        Self {
+            per_minute: calls,
+            burst: calls,
+        }
+    }
+
+    /// Limit how many calls can be made back to back
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// Budgets enforced by a [`RateLimiter`]
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// Budget shared by all APIs
+    pub global: Option<RateLimit>,
+    /// Per-API budgets, keyed by API name
+    pub api_limits: HashMap<String, RateLimit>,
+    /// Daily quota shared by all APIs
+    pub global_daily_quota: Option<u32>,
+    /// Per-API daily quotas, keyed by API name
+    pub daily_quotas: HashMap<String, u32>,
+}
+
+impl RateLimitConfig {
+    /// Configuration without any limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the budget shared by all APIs
+    pub fn with_global_limit(mut self, limit: RateLimit) -> Self {
+        self.global = Some(limit);
+        self
+    }
+
+    /// Set the budget of one API
+    pub fn with_api_limit(mut self, api: Api, limit: RateLimit) -> Self {
+        self.api_limits.insert(api.name(), limit);
+        self
+    }
+
+    /// Set the daily quota shared by all APIs
+    pub fn with_global_daily_quota(mut self, calls: u32) -> Self {
+        self.global_daily_quota = Some(calls);
+        self
+    }
+
+    /// Set the daily quota of one API
+    pub fn with_daily_quota(mut self, api: Api, calls: u32) -> Self {
+        self.daily_quotas.insert(api.name(), calls);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    capacity: f64,
+    per_second: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        Self {
+            tokens: capacity,
+            capacity,
+            per_second: f64::from(limit.per_minute) / 60.0,
+            refilled_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Time until one token is available
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else if self.per_second <= 0.0 {
+            Duration::MAX
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.per_second)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DailyCounter {
+    day: u64,
+    global: u32,
+    per_api: HashMap<String, u32>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    global: Option<Bucket>,
+    apis: HashMap<String, Bucket>,
+    daily: DailyCounter,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: RateLimitConfig,
+    state: Mutex<LimiterState>,
+}
+
+/// Outcome of a non-blocking reservation attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reservation {
+    /// The call may proceed; it has been counted
+    Granted,
+    /// No token is available yet; try again after the given delay
+    Wait(Duration),
+    /// A daily quota is used up until it resets after the given delay
+    QuotaExhausted(Duration),
+}
+
+/// Shareable rate limiter handle; clones draw from the same budget
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    shared: Arc<Shared>,
+}
+
+impl RateLimiter {
+    /// Create a limiter with full buckets and unused daily quotas
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                config,
+                state: Mutex::new(LimiterState::default()),
+            }),
+        }
+    }
+
+    /// Limiter configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.shared.config
+    }
+
+    /// Try to reserve one call of `api_name` without waiting
+    pub fn try_acquire(&self, api_name: &str) -> Reservation {
+        self.reserve(api_name, Instant::now(), quota_day_now())
+    }
+
+    /// Reserve one call of `api_name`, waiting for bucket tokens as needed.
+    ///
+    /// Returns how long the call waited, or [`TushareError::QuotaExhausted`] if a daily
+    /// quota is used up.
+    pub async fn acquire(&self, api_name: &str) -> TushareResult<Duration> {
+        let mut waited = Duration::ZERO;
+        loop {
+            match self.try_acquire(api_name) {
+                Reservation::Granted => return Ok(waited),
+                Reservation::Wait(wait) => {
+                    tokio::time::sleep(wait).await;
+                    waited += wait;
+                }
+                Reservation::QuotaExhausted(resets_in) => {
+                    return Err(TushareError::QuotaExhausted {
+                        api_name: api_name.to_string(),
+                        resets_in,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Calls of `api_name` counted today (Beijing time)
+    pub fn daily_used(&self, api_name: &str) -> u32 {
+        let mut state = self.lock_state();
+        roll_day(&mut state.daily, quota_day_now().0);
+        state.daily.per_api.get(api_name).copied().unwrap_or(0)
+    }
+
+    /// Calls of `api_name` still allowed today, if it has a daily quota
+    pub fn daily_remaining(&self, api_name: &str) -> Option<u32> {
+        let config = &self.shared.config;
+        let mut state = self.lock_state();
+        roll_day(&mut state.daily, quota_day_now().0);
+        let api = config
+            .daily_quotas
+            .get(api_name)
+            .map(|quota| quota.saturating_sub(state.daily.per_api.get(api_name).copied().unwrap_or(0)));
+        let global = config
+            .global_daily_quota
+            .map(|quota| quota.saturating_sub(state.daily.global));
+        match (api, global) {
+            (Some(a), Some(g)) => Some(a.min(g)),
+            (a, g) => a.or(g),
+        }
+    }
+
+    fn reserve(&self, api_name: &str, now: Instant, (day, resets_in): (u64, Duration)) -> Reservation {
+        let config = &self.shared.config;
+        let mut state = self.lock_state();
+        let LimiterState { global, apis, daily } = &mut *state;
+
+        roll_day(daily, day);
+        let api_used = daily.per_api.get(api_name).copied().unwrap_or(0);
+        let over_api_quota = config.daily_quotas.get(api_name).is_some_and(|quota| api_used >= *quota);
+        let over_global_quota = config.global_daily_quota.is_some_and(|quota| daily.global >= quota);
+        if over_api_quota || over_global_quota {
+            return Reservation::QuotaExhausted(resets_in);
+        }
+
+        let global = config
+            .global
+            .map(|limit| global.get_or_insert_with(|| Bucket::new(limit, now)));
+        let api = config
+            .api_limits
+            .get(api_name)
+            .map(|limit| apis.entry(api_name.to_string()).or_insert_with(|| Bucket::new(*limit, now)));
+
+        let mut buckets: Vec<&mut Bucket> = global.into_iter().chain(api).collect();
+        for bucket in buckets.iter_mut() {
+            bucket.refill(now);
+        }
+        let wait = buckets.iter().map(|bucket| bucket.wait()).max().unwrap_or_default();
+        if !wait.is_zero() {
+            return Reservation::Wait(wait.max(Duration::from_millis(1)));
+        }
+
+        for bucket in buckets {
+            bucket.tokens -= 1.0;
+        }
+        daily.global += 1;
+        *daily.per_api.entry(api_name.to_string()).or_insert(0) += 1;
+        Reservation::Granted
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn roll_day(daily: &mut DailyCounter, day: u64) {
+    if daily.day != day {
+        *daily = DailyCounter {
+            day,
+            ..DailyCounter::default()
+        };
+    }
+}
+
+/// Current quota day (days since the epoch, Beijing time) and time until it ends
+fn quota_day_now() -> (u64, Duration) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + QUOTA_DAY_OFFSET_SECS;
+    (secs / SECS_PER_DAY, Duration::from_secs(SECS_PER_DAY - secs % SECS_PER_DAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: (u64, Duration) = (1, Duration::from_secs(3600));
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let limiter = RateLimiter::new(
+            RateLimitConfig::new().with_api_limit(Api::Daily, RateLimit::per_minute(60).with_burst(2)),
+        );
+        let start = Instant::now();
+        assert_eq!(limiter.reserve("daily", start, DAY), Reservation::Granted);
+        assert_eq!(limiter.reserve("daily", start, DAY), Reservation::Granted);
+        assert!(matches!(limiter.reserve("daily", start, DAY), Reservation::Wait(wait) if wait <= Duration::from_secs(1)));
+        // Other APIs are not limited
+        assert_eq!(limiter.reserve("stock_basic", start, DAY), Reservation::Granted);
+        assert_eq!(limiter.reserve("daily", start + Duration::from_secs(1), DAY), Reservation::Granted);
+    }
+
+    #[test]
+    fn global_budget_is_shared_between_clones() {
+        let limiter = RateLimiter::new(RateLimitConfig::new().with_global_limit(RateLimit::per_minute(2)));
+        let other = limiter.clone();
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("daily", now, DAY), Reservation::Granted);
+        assert_eq!(other.reserve("weekly", now, DAY), Reservation::Granted);
+        assert!(matches!(other.reserve("daily", now, DAY), Reservation::Wait(_)));
+    }
+
+    #[test]
+    fn daily_quota_resets_on_the_next_day() {
+        let limiter = RateLimiter::new(RateLimitConfig::new().with_daily_quota(Api::Daily, 1));
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("daily", now, DAY), Reservation::Granted);
+        assert_eq!(limiter.reserve("daily", now, DAY), Reservation::QuotaExhausted(DAY.1));
+        assert_eq!(limiter.reserve("daily", now, (2, DAY.1)), Reservation::Granted);
+    }
+}
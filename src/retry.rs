@@ -0,0 +1,289 @@
+//! Retry policies for [`TushareClientEx`](crate::TushareClientEx)
+//!
+//! A [`RetryPolicy`] looks at each failed attempt and decides whether to try again and
+//! how long to wait first. [`DefaultRetryPolicy`] retries:
+//!
+//! - network failures and timeouts, with exponential backoff and jitter;
+//! - HTTP 5xx responses, honoring a `Retry-After` header when present;
+//! - truncated or otherwise invalid JSON bodies;
+//! - Tushare per-minute throttling replies ([`ErrorKind::RateLimited`]), waiting until the
+//!   next minute window (or the `Retry-After` delay of an HTTP 429).
+//!
+//! Hourly and daily caps ([`ErrorKind::QuotaExhausted`]) fail fast: they do not reset
+//! before midnight Beijing time, so retrying them would only burn attempts.
+//!
+//! It stops after `max_retries` retries or once the optional total time budget would be
+//! exceeded. Extra conditions can be added with [`DefaultRetryPolicy::with_retry_if`], or a
+//! completely custom policy can be supplied by implementing [`RetryPolicy`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::{ErrorKind, TushareClient, TushareClientEx};
+//! use tushare_api::retry::DefaultRetryPolicy;
+//!
+//! # fn build(inner: TushareClient) -> TushareClientEx {
+//! TushareClientEx::new(inner).with_retry_policy(
+//!     DefaultRetryPolicy::new()
+//!         .with_max_retries(5)
+//!         .with_total_budget(Duration::from_secs(180))
+//!         .with_retry_if(|err| err.kind() == ErrorKind::OtherApi && err.api_code() == Some(-1)),
+//! )
+//! # }
+//! ```
+
+use crate::client_ex::RetryConfig;
+use crate::error::{ErrorKind, TushareError};
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A failed attempt, as seen by a [`RetryPolicy`]
+#[derive(Debug)]
+pub struct RetryContext<'a> {
+    /// Number of retries already made (0 after the first attempt failed)
+    pub retries: usize,
+    /// Time spent on the call so far, including earlier attempts and waits
+    pub elapsed: Duration,
+    /// Error of the attempt that just failed
+    pub error: &'a TushareError,
+}
+
+/// What to do after a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Wait for the given delay, then try again
+    Retry(Duration),
+    /// Give up and return the error
+    Stop,
+}
+
+/// Decides whether and when a failed call is retried
+pub trait RetryPolicy: Send + Sync + fmt::Debug {
+    /// Decide what to do after a failed attempt
+    fn decide(&self, context: &RetryContext<'_>) -> RetryDecision;
+}
+
+impl<T: RetryPolicy + ?Sized> RetryPolicy for Arc<T> {
+    fn decide(&self, context: &RetryContext<'_>) -> RetryDecision {
+        (**self).decide(context)
+    }
+}
+
+type RetryPredicate = dyn Fn(&TushareError) -> bool + Send + Sync;
+
+/// Retry policy used by [`TushareClientEx::with_retry_config`](crate::TushareClientEx::with_retry_config)
+#[derive(Clone)]
+pub struct DefaultRetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: usize,
+    /// First backoff delay; doubled on every retry
+    pub base_delay: Duration,
+    /// Upper bound of the backoff delay
+    pub max_delay: Duration,
+    /// Give up once the call would take longer than this in total
+    pub total_budget: Option<Duration>,
+    /// Retry Tushare per-minute throttling replies, waiting for the next minute window
+    pub retry_rate_limited: bool,
+    /// Use the `Retry-After` header of HTTP errors as the delay
+    pub honor_retry_after: bool,
+    predicates: Vec<Arc<RetryPredicate>>,
+}
+
+impl Default for DefaultRetryPolicy {
+    fn default() -> Self {
+        Self::from(RetryConfig::default())
+    }
+}
+
+impl From<RetryConfig> for DefaultRetryPolicy {
+    fn from(config: RetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: config.base_delay,
+            max_delay: config.max_delay,
+            total_budget: None,
+            retry_rate_limited: true,
+            honor_retry_after: true,
+            predicates: Vec::new(),
+        }
+    }
+}
+
+impl DefaultRetryPolicy {
+    /// Policy with the [`RetryConfig::default`] backoff settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of retries
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the backoff delays
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Give up once the call, including waits, would exceed `budget`
+    pub fn with_total_budget(mut self, budget: Duration) -> Self {
+        self.total_budget = Some(budget);
+        self
+    }
+
+    /// Enable or disable retrying Tushare per-minute throttling replies
+    pub fn with_retry_rate_limited(mut self, enabled: bool) -> Self {
+        self.retry_rate_limited = enabled;
+        self
+    }
+
+    /// Enable or disable honoring `Retry-After`
+    pub fn with_honor_retry_after(mut self, enabled: bool) -> Self {
+        self.honor_retry_after = enabled;
+        self
+    }
+
+    /// Also retry errors matching `predicate` (with exponential backoff)
+    pub fn with_retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&TushareError) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(Arc::new(predicate));
+        self
+    }
+
+    /// Delay before retrying `error`, or `None` if it is not retried
+    fn delay_for(&self, error: &TushareError, retries: usize) -> Option<Duration> {
+        let retry_after = match error.root() {
+            TushareError::HttpStatus { retry_after, .. } if self.honor_retry_after => *retry_after,
+            _ => None,
+        };
+
+        match error.kind() {
+            ErrorKind::RateLimited if !self.retry_rate_limited => None,
+            ErrorKind::RateLimited => Some(retry_after.unwrap_or_else(until_next_minute)),
+            ErrorKind::QuotaExhausted => None,
+            ErrorKind::Network | ErrorKind::Timeout | ErrorKind::ServerError | ErrorKind::Serialization => {
+                Some(retry_after.unwrap_or_else(|| self.backoff(retries)))
+            }
+            _ if self.predicates.iter().any(|predicate| predicate(error)) => Some(self.backoff(retries)),
+            _ => None,
+        }
+    }
+
+    /// Exponential backoff with equal jitter: `capped/2 + random(0..=capped/2)`
+    fn backoff(&self, retries: usize) -> Duration {
+        let shift = retries.min(31) as u32;
+        let factor = 1u64.checked_shl(shift).unwrap_or(u64::MAX);
+        let base = self.base_delay.saturating_mul(factor as u32);
+        let capped = if base > self.max_delay { self.max_delay } else { base };
+
+        // Compared to full jitter, this is less volatile while still spreading retries.
+        let capped_ms = capped.as_millis().min(u64::MAX as u128) as u64;
+        if capped_ms == 0 {
+            return Duration::from_millis(0);
+        }
+
+        let half = capped_ms / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0..=half);
+        Duration::from_millis(half + jitter_ms)
+    }
+}
+
+impl RetryPolicy for DefaultRetryPolicy {
+    fn decide(&self, context: &RetryContext<'_>) -> RetryDecision {
+        if context.retries >= self.max_retries {
+            return RetryDecision::Stop;
+        }
+        let Some(delay) = self.delay_for(context.error, context.retries) else {
+            return RetryDecision::Stop;
+        };
+        if let Some(budget) = self.total_budget
+            && context.elapsed.saturating_add(delay) > budget
+        {
+            return RetryDecision::Stop;
+        }
+        RetryDecision::Retry(delay)
+    }
+}
+
+impl fmt::Debug for DefaultRetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DefaultRetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("total_budget", &self.total_budget)
+            .field("retry_rate_limited", &self.retry_rate_limited)
+            .field("honor_retry_after", &self.honor_retry_after)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+/// Time until the next wall-clock minute starts, when per-minute limits reset
+fn until_next_minute() -> Duration {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let into_minute = Duration::from_millis((now.as_millis() % 60_000) as u64);
+    Duration::from_secs(60) - into_minute + Duration::from_millis(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decide(policy: &DefaultRetryPolicy, error: &TushareError, retries: usize, elapsed: Duration) -> RetryDecision {
+        policy.decide(&RetryContext { retries, elapsed, error })
+    }
+
+    fn api_error(code: i32, message: &str) -> TushareError {
+        TushareError::ApiError {
+            code,
+            message: message.to_string(),
+            request_id: "r1".to_string(),
+        }
+    }
+
+    #[test]
+    fn retries_throttling_and_server_errors() {
+        let policy = DefaultRetryPolicy::new();
+        let throttled = api_error(40203, "抱歉，您每分钟最多访问该接口500次");
+        assert!(matches!(
+            decide(&policy, &throttled, 0, Duration::ZERO),
+            RetryDecision::Retry(delay) if delay <= Duration::from_millis(60_100)
+        ));
+
+        let bad_gateway = TushareError::HttpStatus {
+            status: 502,
+            retry_after: Some(Duration::from_secs(7)),
+            body: "<html>Bad Gateway</html>".to_string(),
+        };
+        assert_eq!(decide(&policy, &bad_gateway, 0, Duration::ZERO), RetryDecision::Retry(Duration::from_secs(7)));
+
+        assert_eq!(decide(&policy, &api_error(40203, "没有权限"), 0, Duration::ZERO), RetryDecision::Stop);
+        for capped in ["抱歉，您每小时最多访问该接口2次", "抱歉，您每天最多访问该接口100000次"] {
+            assert_eq!(decide(&policy, &api_error(40203, capped), 0, Duration::ZERO), RetryDecision::Stop);
+        }
+        assert_eq!(decide(&policy, &TushareError::TimeoutError, 3, Duration::ZERO), RetryDecision::Stop);
+    }
+
+    #[test]
+    fn budget_and_custom_predicates() {
+        let policy = DefaultRetryPolicy::new()
+            .with_total_budget(Duration::from_secs(10))
+            .with_retry_if(|err| err.api_code() == Some(-1));
+        let bad_gateway = TushareError::HttpStatus {
+            status: 503,
+            retry_after: Some(Duration::from_secs(7)),
+            body: String::new(),
+        };
+        assert_eq!(decide(&policy, &bad_gateway, 0, Duration::from_secs(5)), RetryDecision::Stop);
+        assert!(matches!(decide(&policy, &api_error(-1, "系统繁忙"), 0, Duration::ZERO), RetryDecision::Retry(_)));
+    }
+}
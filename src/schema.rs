@@ -0,0 +1,440 @@
+//! Input and output schemas of core APIs, and local request validation
+//!
+//! A misspelled parameter is silently ignored by the server and a misspelled field comes back
+//! as a vague API error after a round-trip. [`Api::schema`](crate::Api::schema) describes the
+//! parameters an API accepts (with required flags and formats) and the fields it returns
+//! (with types and units), as documented by Tushare. [`TushareRequest::validate`] checks a
+//! request against it; with
+//! [`TushareClientBuilder::with_strict_validation`](crate::client::TushareClientBuilder::with_strict_validation)
+//! the client runs that check before every call and fails with
+//! [`TushareError::InvalidRequest`] without sending anything.
+//!
+//! Schemas are recorded for `stock_basic`, `trade_cal`, `daily`, `weekly`, `monthly`,
+//! `adj_factor`, `daily_basic`, `moneyflow`, `index_basic`, `index_daily` and `fund_daily`.
+//! Requests to other APIs pass [`TushareRequest::validate`]; a strict client sends them with a
+//! logged warning, unless the API is listed with
+//! [`TushareClientBuilder::with_unvalidated_api`](crate::client::TushareClientBuilder::with_unvalidated_api).
+//! The `offset` and `limit` paging parameters are accepted for every API.
+//!
+//! # Example
+//!
+//! ```rust
+//! use tushare_api::{Api, TushareRequest};
+//!
+//! let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ"), ("start_date", "2024-01-01")], ["close", "volume"]);
+//! let err = request.validate().unwrap_err();
+//! assert!(err.to_string().contains("start_date"));
+//! assert!(err.to_string().contains("volume"));
+//!
+//! let unit = Api::Daily.schema().and_then(|schema| schema.field("amount")).and_then(|field| field.unit);
+//! assert_eq!(unit, Some("千元"));
+//! ```
+
+use crate::api::Api;
+use crate::dates;
+use crate::error::{TushareError, TushareResult};
+use crate::pagination::{LIMIT_PARAM, OFFSET_PARAM};
+use crate::types::TushareRequest;
+
+/// Expected format of a parameter value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamFormat {
+    /// Any string
+    Text,
+    /// A `YYYYMMDD` date
+    Date,
+    /// An integer
+    Integer,
+}
+
+/// An input parameter of an API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub required: bool,
+    pub format: ParamFormat,
+}
+
+/// Type of an output field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Str,
+    Float,
+    Int,
+    /// A `YYYYMMDD` date string
+    Date,
+}
+
+/// An output field of an API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub ty: FieldType,
+    /// Unit of numeric values, e.g. `千元`
+    pub unit: Option<&'static str>,
+}
+
+/// Parameters and fields of an API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiSchema {
+    pub params: &'static [ParamSpec],
+    pub fields: &'static [FieldSpec],
+}
+
+impl ApiSchema {
+    /// The parameter called `name`
+    pub fn param(&self, name: &str) -> Option<&'static ParamSpec> {
+        self.params.iter().find(|param| param.name == name)
+    }
+
+    /// The field called `name`
+    pub fn field(&self, name: &str) -> Option<&'static FieldSpec> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+const fn optional(name: &'static str, format: ParamFormat) -> ParamSpec {
+    ParamSpec { name, required: false, format }
+}
+
+const fn required(name: &'static str, format: ParamFormat) -> ParamSpec {
+    ParamSpec { name, required: true, format }
+}
+
+const fn field(name: &'static str, ty: FieldType) -> FieldSpec {
+    FieldSpec { name, ty, unit: None }
+}
+
+const fn measure(name: &'static str, ty: FieldType, unit: &'static str) -> FieldSpec {
+    FieldSpec { name, ty, unit: Some(unit) }
+}
+
+use FieldType::{Date, Float, Int, Str};
+use ParamFormat::Text;
+
+const QUOTE_PARAMS: &[ParamSpec] = &[
+    optional("ts_code", Text),
+    optional("trade_date", ParamFormat::Date),
+    optional("start_date", ParamFormat::Date),
+    optional("end_date", ParamFormat::Date),
+];
+
+const QUOTE_FIELDS: &[FieldSpec] = &[
+    field("ts_code", Str),
+    field("trade_date", Date),
+    measure("open", Float, "元"),
+    measure("high", Float, "元"),
+    measure("low", Float, "元"),
+    measure("close", Float, "元"),
+    measure("pre_close", Float, "元"),
+    measure("change", Float, "元"),
+    measure("pct_chg", Float, "%"),
+    measure("vol", Float, "手"),
+    measure("amount", Float, "千元"),
+];
+
+/// Index levels are in points, volumes and amounts as for stocks
+const INDEX_FIELDS: &[FieldSpec] = &[
+    field("ts_code", Str),
+    field("trade_date", Date),
+    measure("open", Float, "点"),
+    measure("high", Float, "点"),
+    measure("low", Float, "点"),
+    measure("close", Float, "点"),
+    measure("pre_close", Float, "点"),
+    measure("change", Float, "点"),
+    measure("pct_chg", Float, "%"),
+    measure("vol", Float, "手"),
+    measure("amount", Float, "千元"),
+];
+
+const STOCK_BASIC: ApiSchema = ApiSchema {
+    params: &[
+        optional("ts_code", Text),
+        optional("name", Text),
+        optional("market", Text),
+        optional("list_status", Text),
+        optional("exchange", Text),
+        optional("is_hs", Text),
+    ],
+    fields: &[
+        field("ts_code", Str),
+        field("symbol", Str),
+        field("name", Str),
+        field("area", Str),
+        field("industry", Str),
+        field("fullname", Str),
+        field("enname", Str),
+        field("cnspell", Str),
+        field("market", Str),
+        field("exchange", Str),
+        field("curr_type", Str),
+        field("list_status", Str),
+        field("list_date", Date),
+        field("delist_date", Date),
+        field("is_hs", Str),
+        field("act_name", Str),
+        field("act_ent_type", Str),
+    ],
+};
+
+const TRADE_CAL: ApiSchema = ApiSchema {
+    params: &[
+        optional("exchange", Text),
+        optional("start_date", ParamFormat::Date),
+        optional("end_date", ParamFormat::Date),
+        optional("is_open", ParamFormat::Integer),
+    ],
+    fields: &[
+        field("exchange", Str),
+        field("cal_date", Date),
+        field("is_open", Int),
+        field("pretrade_date", Date),
+    ],
+};
+
+const QUOTES: ApiSchema = ApiSchema {
+    params: QUOTE_PARAMS,
+    fields: QUOTE_FIELDS,
+};
+
+const INDEX_DAILY: ApiSchema = ApiSchema {
+    params: &[
+        required("ts_code", Text),
+        optional("trade_date", ParamFormat::Date),
+        optional("start_date", ParamFormat::Date),
+        optional("end_date", ParamFormat::Date),
+    ],
+    fields: INDEX_FIELDS,
+};
+
+const ADJ_FACTOR: ApiSchema = ApiSchema {
+    params: QUOTE_PARAMS,
+    fields: &[field("ts_code", Str), field("trade_date", Date), field("adj_factor", Float)],
+};
+
+const DAILY_BASIC: ApiSchema = ApiSchema {
+    params: QUOTE_PARAMS,
+    fields: &[
+        field("ts_code", Str),
+        field("trade_date", Date),
+        measure("close", Float, "元"),
+        measure("turnover_rate", Float, "%"),
+        measure("turnover_rate_f", Float, "%"),
+        field("volume_ratio", Float),
+        field("pe", Float),
+        field("pe_ttm", Float),
+        field("pb", Float),
+        field("ps", Float),
+        field("ps_ttm", Float),
+        measure("dv_ratio", Float, "%"),
+        measure("dv_ttm", Float, "%"),
+        measure("total_share", Float, "万股"),
+        measure("float_share", Float, "万股"),
+        measure("free_share", Float, "万股"),
+        measure("total_mv", Float, "万元"),
+        measure("circ_mv", Float, "万元"),
+    ],
+};
+
+const MONEYFLOW: ApiSchema = ApiSchema {
+    params: QUOTE_PARAMS,
+    fields: &[
+        field("ts_code", Str),
+        field("trade_date", Date),
+        measure("buy_sm_vol", Int, "手"),
+        measure("buy_sm_amount", Float, "万元"),
+        measure("sell_sm_vol", Int, "手"),
+        measure("sell_sm_amount", Float, "万元"),
+        measure("buy_md_vol", Int, "手"),
+        measure("buy_md_amount", Float, "万元"),
+        measure("sell_md_vol", Int, "手"),
+        measure("sell_md_amount", Float, "万元"),
+        measure("buy_lg_vol", Int, "手"),
+        measure("buy_lg_amount", Float, "万元"),
+        measure("sell_lg_vol", Int, "手"),
+        measure("sell_lg_amount", Float, "万元"),
+        measure("buy_elg_vol", Int, "手"),
+        measure("buy_elg_amount", Float, "万元"),
+        measure("sell_elg_vol", Int, "手"),
+        measure("sell_elg_amount", Float, "万元"),
+        measure("net_mf_vol", Int, "手"),
+        measure("net_mf_amount", Float, "万元"),
+    ],
+};
+
+const INDEX_BASIC: ApiSchema = ApiSchema {
+    params: &[
+        optional("ts_code", Text),
+        optional("name", Text),
+        optional("market", Text),
+        optional("publisher", Text),
+        optional("category", Text),
+    ],
+    fields: &[
+        field("ts_code", Str),
+        field("name", Str),
+        field("fullname", Str),
+        field("market", Str),
+        field("publisher", Str),
+        field("index_type", Str),
+        field("category", Str),
+        field("base_date", Date),
+        field("base_point", Float),
+        field("list_date", Date),
+        field("weight_rule", Str),
+        field("desc", Str),
+        field("exp_date", Date),
+    ],
+};
+
+/// Schema of `api`, if recorded
+pub(crate) fn schema_of(api: &Api) -> Option<&'static ApiSchema> {
+    match api {
+        Api::StockBasic => Some(&STOCK_BASIC),
+        Api::TradeCal => Some(&TRADE_CAL),
+        // Exchange-traded funds quote in the same units as stocks
+        Api::Daily | Api::Weekly | Api::Monthly | Api::FundDaily => Some(&QUOTES),
+        Api::IndexDaily => Some(&INDEX_DAILY),
+        Api::AdjFactor => Some(&ADJ_FACTOR),
+        Api::DailyBasic => Some(&DAILY_BASIC),
+        Api::Moneyflow => Some(&MONEYFLOW),
+        Api::IndexBasic => Some(&INDEX_BASIC),
+        _ => None,
+    }
+}
+
+/// Check `request` against the schema of its API, reporting every problem at once
+pub(crate) fn validate(request: &TushareRequest) -> TushareResult<()> {
+    let Some(schema) = request.api_name.schema() else {
+        return Ok(());
+    };
+
+    let mut names: Vec<&String> = request.params.keys().collect();
+    names.sort();
+    let mut problems = Vec::new();
+    for name in names {
+        if name == OFFSET_PARAM || name == LIMIT_PARAM {
+            continue;
+        }
+        let value = &request.params[name];
+        match schema.param(name).map(|param| param.format) {
+            None => problems.push(format!("unknown parameter '{name}'")),
+            Some(ParamFormat::Date) if dates::parse_date(value).is_err() => {
+                problems.push(format!("parameter '{name}' must be a YYYYMMDD date, got '{value}'"))
+            }
+            Some(ParamFormat::Integer) if value.parse::<i64>().is_err() => {
+                problems.push(format!("parameter '{name}' must be an integer, got '{value}'"))
+            }
+            Some(_) => {}
+        }
+    }
+    for param in schema.params.iter().filter(|param| param.required) {
+        if !request.params.contains_key(param.name) {
+            problems.push(format!("missing required parameter '{}'", param.name));
+        }
+    }
+    for name in &request.fields {
+        if schema.field(name).is_none() {
+            problems.push(format!("unknown field '{name}'"));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(TushareError::InvalidRequest {
+            api_name: request.api_name.name(),
+            problems,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::middleware::{Middleware, RequestContext};
+    use crate::transport::fixtures::rows_transport;
+    use crate::transport::BoxFuture;
+    use crate::{Api, ErrorKind, TushareClient, TushareError, TushareRequest, TushareResponse, TushareResult};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// Fills in the required `ts_code` of index requests
+    #[derive(Debug)]
+    struct DefaultIndex;
+
+    impl Middleware for DefaultIndex {
+        fn before<'a>(
+            &'a self,
+            _context: &'a mut RequestContext,
+            request: &'a mut TushareRequest,
+        ) -> BoxFuture<'a, TushareResult<Option<TushareResponse>>> {
+            Box::pin(async move {
+                request.params.entry("ts_code".to_string()).or_insert_with(|| "000300.SH".to_string());
+                Ok(None)
+            })
+        }
+    }
+
+    #[test]
+    fn reports_unknown_names_missing_params_and_bad_dates() {
+        let request = TushareRequest::new(
+            Api::IndexDaily,
+            [("trade_date", "20240230"), ("tscode", "000001.SH"), ("limit", "100")],
+            ["close", "clsoe"],
+        );
+        let Err(TushareError::InvalidRequest { api_name, problems }) = request.validate() else {
+            panic!("expected an invalid request");
+        };
+        assert_eq!(api_name, "index_daily");
+        assert_eq!(
+            problems,
+            [
+                "parameter 'trade_date' must be a YYYYMMDD date, got '20240230'",
+                "unknown parameter 'tscode'",
+                "missing required parameter 'ts_code'",
+                "unknown field 'clsoe'",
+            ]
+        );
+
+        let custom = TushareRequest::new(Api::Custom("stk_auction".to_string()), [("anything", "x")], ["y"]);
+        assert!(custom.validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn strict_client_rejects_invalid_requests_locally() {
+        let transport = Arc::new(rows_transport(&["close"], vec![json!([10.5])]));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_strict_validation(true)
+            .build()
+            .unwrap();
+
+        let typo = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["clsoe"]);
+        let err = client.call_api(&typo).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidParameter);
+        assert!(transport.requests().is_empty());
+
+        let valid = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ")], ["close"]);
+        assert!(client.call_api(&valid).await.is_ok());
+
+        // Validated after the middlewares, which may supply required params
+        let without_code = TushareRequest::new(Api::IndexDaily, [("start_date", "20240101")], ["close"]);
+        assert!(client.call_api(&without_code).await.is_err());
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_strict_validation(true)
+            .with_middleware(DefaultIndex)
+            .build()
+            .unwrap();
+        assert!(client.call_api(&without_code).await.is_ok());
+        assert_eq!(transport.requests().len(), 2);
+
+        // APIs without a schema are sent unvalidated
+        let auction = TushareRequest::new(Api::Custom("stk_auction".to_string()), [("trade_date", "20240102")], ["price"]);
+        assert!(client.call_api(&auction).await.is_ok());
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
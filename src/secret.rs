@@ -0,0 +1,126 @@
+//! Redacting container for Tushare tokens
+//!
+//! [`SecretToken`] never prints the token: `Debug` and `Display` show a masked preview
+//! such as `3f2a****`, and the token's memory is zeroized when the value is dropped.
+
+use crate::error::{TushareError, TushareResult};
+use std::fmt;
+use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Number of leading characters kept in the masked preview
+const PREVIEW_CHARS: usize = 4;
+
+/// A Tushare token that is masked when formatted and zeroized on drop
+#[derive(Clone)]
+pub struct SecretToken(String);
+
+impl SecretToken {
+    /// Wrap a token; surrounding whitespace is trimmed
+    pub fn new<S: Into<String>>(token: S) -> Self {
+        let mut raw = token.into();
+        let token = raw.trim().to_string();
+        raw.zeroize();
+        Self(token)
+    }
+
+    /// Read a token from a file, e.g. a mounted Kubernetes secret.
+    ///
+    /// Surrounding whitespace (such as a trailing newline) is trimmed. An empty file
+    /// yields [`TushareError::InvalidToken`].
+    pub fn from_file<P: AsRef<Path>>(path: P) -> TushareResult<Self> {
+        let path = path.as_ref();
+        let content = Zeroizing::new(std::fs::read_to_string(path).map_err(|e| {
+            TushareError::Other(format!("Failed to read token file {}: {e}", path.display()))
+        })?);
+
+        let token = Self::new(content.as_str());
+        if token.is_empty() {
+            return Err(TushareError::InvalidToken);
+        }
+        Ok(token)
+    }
+
+    /// The raw token, for building the request body
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the token is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Masked preview: the first few characters followed by `****`.
+    ///
+    /// Short tokens are masked entirely.
+    pub fn masked(&self) -> String {
+        if self.0.chars().count() <= PREVIEW_CHARS * 2 {
+            return "****".to_string();
+        }
+        let preview: String = self.0.chars().take(PREVIEW_CHARS).collect();
+        format!("{preview}****")
+    }
+}
+
+impl Drop for SecretToken {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SecretToken").field(&self.masked()).finish()
+    }
+}
+
+impl fmt::Display for SecretToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.masked())
+    }
+}
+
+impl From<String> for SecretToken {
+    fn from(token: String) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<&str> for SecretToken {
+    fn from(token: &str) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<&String> for SecretToken {
+    fn from(token: &String) -> Self {
+        Self::new(token.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_masks_the_token() {
+        let token = SecretToken::new("3f2a9c0d1e2b3a4f5e6d");
+        assert_eq!(token.to_string(), "3f2a****");
+        assert_eq!(format!("{token:?}"), "SecretToken(\"3f2a****\")");
+        assert_eq!(SecretToken::new("short").masked(), "****");
+    }
+
+    #[test]
+    fn reads_and_trims_token_file() {
+        let path = std::env::temp_dir().join(format!("tushare-token-{}", crate::client::generate_request_id()));
+        std::fs::write(&path, "file-token-value\n").unwrap();
+        assert_eq!(SecretToken::from_file(&path).unwrap().expose(), "file-token-value");
+
+        std::fs::write(&path, "  \n").unwrap();
+        assert!(matches!(SecretToken::from_file(&path), Err(TushareError::InvalidToken)));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(SecretToken::from_file(&path).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! Single-flight deduplication of identical in-flight requests
+//!
+//! With single-flight enabled (see
+//! [`with_single_flight`](crate::client::TushareClientBuilder::with_single_flight)), concurrent
+//! calls whose requests have the same [`RequestKey`] share one HTTP call: the first caller
+//! sends it and every caller receives a clone of the response. An error handed to several
+//! callers is returned to each of them as [`TushareError::Shared`], which reports the same
+//! [`kind`](TushareError::kind) as the original error; a caller whose request nobody joined
+//! gets the original error.
+//!
+//! If the caller that sent the request is cancelled, one of the waiting callers sends it again.
+
+use crate::error::{TushareError, TushareResult};
+use crate::types::{RequestKey, TushareResponse};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+type Outcome = Result<TushareResponse, Arc<TushareError>>;
+type InFlight = Mutex<HashMap<RequestKey, watch::Receiver<Option<Outcome>>>>;
+
+/// Whether a call sent its own request or joined one already in flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FlightRole {
+    Leader,
+    Follower,
+}
+
+/// Table of requests currently in flight
+#[derive(Debug, Default)]
+pub(crate) struct SingleFlight {
+    in_flight: InFlight,
+}
+
+enum Role {
+    Leader(watch::Sender<Option<Outcome>>),
+    Follower(watch::Receiver<Option<Outcome>>),
+}
+
+/// Removes the leader's entry even if its call is cancelled
+struct EntryGuard<'a> {
+    in_flight: &'a InFlight,
+    key: &'a RequestKey,
+}
+
+impl Drop for EntryGuard<'_> {
+    fn drop(&mut self) {
+        lock(self.in_flight).remove(self.key);
+    }
+}
+
+impl SingleFlight {
+    /// Run `call` for `key`, or wait for the identical call already in flight
+    pub(crate) async fn run<F, Fut>(&self, key: RequestKey, call: F) -> (TushareResult<TushareResponse>, FlightRole)
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = TushareResult<TushareResponse>>,
+    {
+        let mut call = Some(call);
+        loop {
+            let role = {
+                let mut in_flight = lock(&self.in_flight);
+                match in_flight.get(&key) {
+                    Some(receiver) => Role::Follower(receiver.clone()),
+                    None => {
+                        let (sender, receiver) = watch::channel(None);
+                        in_flight.insert(key.clone(), receiver);
+                        Role::Leader(sender)
+                    }
+                }
+            };
+
+            match role {
+                Role::Leader(sender) => {
+                    let guard = EntryGuard {
+                        in_flight: &self.in_flight,
+                        key: &key,
+                    };
+                    // `call` is only taken once: a caller that becomes leader never loops again
+                    let call = call.take().expect("single-flight leader runs its call once");
+                    let result = call().await;
+                    drop(guard);
+
+                    // The entry is gone, so no one else can join; a lone leader keeps its error
+                    if result.is_err() && sender.receiver_count() == 0 {
+                        return (result, FlightRole::Leader);
+                    }
+                    let outcome = result.map_err(Arc::new);
+                    sender.send_replace(Some(outcome.clone()));
+                    return (outcome.map_err(TushareError::Shared), FlightRole::Leader);
+                }
+                Role::Follower(mut receiver) => {
+                    let shared = match receiver.wait_for(Option::is_some).await {
+                        Ok(outcome) => outcome.clone(),
+                        // The leader was dropped before finishing; try again
+                        Err(_) => continue,
+                    };
+                    if let Some(outcome) = shared {
+                        return (outcome.map_err(TushareError::Shared), FlightRole::Follower);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn lock(in_flight: &InFlight) -> std::sync::MutexGuard<'_, HashMap<RequestKey, watch::Receiver<Option<Outcome>>>> {
+    in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::fixtures::{error_response, rows_response};
+    use crate::transport::{BoxFuture, Transport, TransportRequest, TransportResponse};
+    use crate::{Api, TushareClient, TushareRequest};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Slow transport that counts the requests it receives
+    #[derive(Debug, Default)]
+    struct SlowTransport {
+        calls: AtomicUsize,
+        failing: bool,
+    }
+
+    impl Transport for SlowTransport {
+        fn send<'a>(&'a self, _request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                if self.failing {
+                    return Ok(error_response(40001, "参数错误"));
+                }
+                Ok(rows_response(&["cal_date"], vec![json!(["20240102"])]))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_requests_share_one_call() {
+        let transport = Arc::new(SlowTransport::default());
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_single_flight(true)
+            .build()
+            .unwrap();
+
+        let request = TushareRequest::new(Api::TradeCal, [("start_date", "20240101"), ("end_date", "20240131")], ["cal_date"]);
+        let reordered = TushareRequest::new(Api::TradeCal, [("end_date", "20240131"), ("start_date", "20240101")], ["cal_date"]);
+        let other = TushareRequest::new(Api::TradeCal, [("start_date", "20240201")], ["cal_date"]);
+
+        let (a, b, c) = tokio::join!(client.call_api(&request), client.call_api(&reordered), client.call_api(&other));
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn followers_share_the_truncation_verdict() {
+        let transport = Arc::new(SlowTransport::default());
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_single_flight(true)
+            .with_row_cap(Api::TradeCal, 1)
+            .with_truncation_policy(crate::truncation::TruncationPolicy::Error)
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(Api::TradeCal, [("start_date", "20240101")], ["cal_date"]);
+
+        let (leader, follower) = tokio::join!(client.call_api(&request), client.call_api(&request));
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 1);
+        for result in [leader, follower] {
+            assert_eq!(result.unwrap_err().kind(), crate::error::ErrorKind::PossiblyTruncated);
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_are_shared_only_when_handed_to_followers() {
+        let transport = Arc::new(SlowTransport {
+            failing: true,
+            ..SlowTransport::default()
+        });
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_single_flight(true)
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(Api::TradeCal, [("start_date", "20240101")], ["cal_date"]);
+
+        let (leader, follower) = tokio::join!(client.call_api(&request), client.call_api(&request));
+        let alone = client.call_api(&request).await;
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+        for result in [leader, follower] {
+            let err = result.unwrap_err();
+            assert!(matches!(err, TushareError::Shared(_)), "{err:?}");
+            assert_eq!(err.api_code(), Some(40001));
+        }
+        let err = alone.unwrap_err();
+        assert!(matches!(err, TushareError::ApiError { code: 40001, .. }), "{err:?}");
+    }
+}
@@ -0,0 +1,418 @@
+//! Pool of Tushare tokens with load balancing, per-token budgets and quarantine
+//!
+//! A [`TushareClient`](crate::TushareClient) always draws its token from a [`TokenPool`];
+//! a client built with a single token simply has a pool of one. Logs only ever show a
+//! token's label, never the token itself.
+//!
+//! When Tushare rejects a token, the pool reacts according to the
+//! [`ErrorKind`](crate::ErrorKind):
+//! - `InvalidToken` and `QuotaExhausted`: the token is quarantined for every API.
+//! - `PermissionDenied`: the token is left out of calls to that one API for the quarantine time.
+//! - `RateLimited`: the token counts as over its per-minute budget until its window frees up.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use tushare_api::TushareClient;
+//! use tushare_api::token_pool::{PooledToken, TokenSelection};
+//!
+//! # fn build() -> tushare_api::TushareResult<TushareClient> {
+//! let client = TushareClient::builder()
+//!     .with_pooled_token(PooledToken::new("team-5000", "token_a").with_per_minute_budget(500))
+//!     .with_pooled_token(PooledToken::new("team-2000", "token_b").with_per_minute_budget(200))
+//!     .with_token_selection(TokenSelection::LeastUsed)
+//!     .with_token_quarantine(Duration::from_secs(300))
+//!     .build()?;
+//! # Ok(client)
+//! # }
+//! ```
+
+use crate::error::{ErrorKind, TushareError, TushareResult};
+use crate::secret::SecretToken;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+/// A token with its label and optional per-minute budget
+#[derive(Debug, Clone)]
+pub struct PooledToken {
+    /// Label shown in logs instead of the token
+    pub label: String,
+    token: SecretToken,
+    /// Maximum number of requests per rolling minute
+    pub per_minute_budget: Option<u32>,
+}
+
+impl PooledToken {
+    /// Create a pooled token without a budget
+    pub fn new<L: Into<String>, T: Into<SecretToken>>(label: L, token: T) -> Self {
+        Self {
+            label: label.into(),
+            token: token.into(),
+            per_minute_budget: None,
+        }
+    }
+
+    /// Create a pooled token read from a file, e.g. a mounted Kubernetes secret
+    pub fn from_file<L: Into<String>, P: AsRef<Path>>(label: L, path: P) -> TushareResult<Self> {
+        Ok(Self::new(label, SecretToken::from_file(path)?))
+    }
+
+    /// Limit the token to `budget` requests per rolling minute
+    pub fn with_per_minute_budget(mut self, budget: u32) -> Self {
+        self.per_minute_budget = Some(budget);
+        self
+    }
+}
+
+/// How the next token is picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenSelection {
+    /// Cycle through the available tokens in order
+    #[default]
+    RoundRobin,
+    /// Pick the token with the fewest requests in the last minute
+    LeastUsed,
+}
+
+/// Usage snapshot of one pooled token
+#[derive(Debug, Clone)]
+pub struct TokenStats {
+    pub label: String,
+    /// Requests started in the last minute
+    pub used_last_minute: usize,
+    /// Requests started since the pool was created
+    pub total_uses: u64,
+    /// Remaining quarantine time, if the token is quarantined
+    pub quarantined_for: Option<Duration>,
+    /// APIs the token is left out of after permission errors
+    pub denied_apis: Vec<String>,
+}
+
+/// Token checked out of the pool for one request
+#[derive(Clone)]
+pub(crate) struct TokenLease {
+    pub(crate) index: usize,
+    pub(crate) label: String,
+    pub(crate) token: SecretToken,
+}
+
+/// Why no token could be checked out right now
+#[derive(Debug)]
+pub(crate) enum AcquireError {
+    /// All usable tokens spent their budget; retry after the given delay
+    Wait(Duration),
+    /// Every token is quarantined or denied the API
+    AllQuarantined(Duration),
+}
+
+/// How the pool reacted to an error of a leased token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenPenalty {
+    /// Taken out of rotation for every API
+    Quarantined(Duration),
+    /// Left out of calls to the failing API
+    Denied(Duration),
+    /// Treated as over its per-minute budget
+    Throttled(Duration),
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    recent: VecDeque<Instant>,
+    total_uses: u64,
+    quarantined_until: Option<Instant>,
+    throttled_until: Option<Instant>,
+    denied_until: HashMap<String, Instant>,
+}
+
+#[derive(Debug)]
+struct PoolState {
+    tokens: Vec<TokenState>,
+    next: usize,
+}
+
+/// Load-balanced pool of tokens
+#[derive(Debug)]
+pub struct TokenPool {
+    tokens: Vec<PooledToken>,
+    selection: TokenSelection,
+    quarantine: Duration,
+    state: Mutex<PoolState>,
+}
+
+impl TokenPool {
+    /// Default time a failing token is taken out of rotation
+    pub const DEFAULT_QUARANTINE: Duration = Duration::from_secs(300);
+
+    /// Create a pool; fails with [`TushareError::InvalidToken`] if `tokens` is empty
+    pub fn new(tokens: Vec<PooledToken>, selection: TokenSelection, quarantine: Duration) -> TushareResult<Self> {
+        if tokens.is_empty() || tokens.iter().any(|t| t.token.is_empty()) {
+            return Err(TushareError::InvalidToken);
+        }
+        let state = PoolState {
+            tokens: tokens.iter().map(|_| TokenState::default()).collect(),
+            next: 0,
+        };
+        Ok(Self {
+            tokens,
+            selection,
+            quarantine,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Pool holding a single token labeled `default`
+    pub fn single<T: Into<SecretToken>>(token: T) -> TushareResult<Self> {
+        Self::new(
+            vec![PooledToken::new("default", token)],
+            TokenSelection::RoundRobin,
+            Self::DEFAULT_QUARANTINE,
+        )
+    }
+
+    /// Number of tokens in the pool
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether the pool is empty (never true for a constructed pool)
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Labels of the pooled tokens, in order
+    pub fn labels(&self) -> Vec<String> {
+        self.tokens.iter().map(|t| t.label.clone()).collect()
+    }
+
+    /// Usage snapshot of every token
+    pub fn stats(&self) -> Vec<TokenStats> {
+        let now = Instant::now();
+        let mut state = self.lock_state();
+        self.tokens
+            .iter()
+            .zip(state.tokens.iter_mut())
+            .map(|(token, token_state)| {
+                prune(&mut token_state.recent, now);
+                token_state.denied_until.retain(|_, until| *until > now);
+                let mut denied_apis: Vec<String> = token_state.denied_until.keys().cloned().collect();
+                denied_apis.sort();
+                TokenStats {
+                    label: token.label.clone(),
+                    used_last_minute: token_state.recent.len(),
+                    total_uses: token_state.total_uses,
+                    quarantined_for: token_state
+                        .quarantined_until
+                        .filter(|until| *until > now)
+                        .map(|until| until - now),
+                    denied_apis,
+                }
+            })
+            .collect()
+    }
+
+    /// Check out a token for one request to `api_name`
+    pub(crate) fn acquire(&self, api_name: &str) -> Result<TokenLease, AcquireError> {
+        let now = Instant::now();
+        let mut state = self.lock_state();
+        let count = self.tokens.len();
+
+        let mut candidates = Vec::with_capacity(count);
+        let mut budget_wait: Option<Duration> = None;
+        let mut quarantine_wait: Option<Duration> = None;
+
+        for offset in 0..count {
+            let index = (state.next + offset) % count;
+            let token_state = &mut state.tokens[index];
+            prune(&mut token_state.recent, now);
+
+            if let Some(until) = token_state.quarantined_until {
+                if until > now {
+                    quarantine_wait = min_duration(quarantine_wait, until - now);
+                    continue;
+                }
+                token_state.quarantined_until = None;
+            }
+            if let Some(until) = token_state.denied_until.get(api_name).copied() {
+                if until > now {
+                    quarantine_wait = min_duration(quarantine_wait, until - now);
+                    continue;
+                }
+                token_state.denied_until.remove(api_name);
+            }
+            if let Some(until) = token_state.throttled_until {
+                if until > now {
+                    budget_wait = min_duration(budget_wait, until - now);
+                    continue;
+                }
+                token_state.throttled_until = None;
+            }
+
+            match self.tokens[index].per_minute_budget {
+                Some(budget) if token_state.recent.len() >= budget as usize => {
+                    let oldest = token_state.recent.front().copied().unwrap_or(now);
+                    budget_wait = min_duration(budget_wait, (oldest + BUDGET_WINDOW).saturating_duration_since(now));
+                }
+                _ => candidates.push(index),
+            }
+        }
+
+        let chosen = match self.selection {
+            TokenSelection::RoundRobin => candidates.first().copied(),
+            TokenSelection::LeastUsed => candidates
+                .iter()
+                .copied()
+                .min_by_key(|&i| (state.tokens[i].recent.len(), state.tokens[i].total_uses)),
+        };
+
+        let Some(index) = chosen else {
+            return Err(match budget_wait {
+                Some(wait) => AcquireError::Wait(wait.max(Duration::from_millis(1))),
+                None => AcquireError::AllQuarantined(quarantine_wait.unwrap_or_default()),
+            });
+        };
+
+        let token_state = &mut state.tokens[index];
+        token_state.recent.push_back(now);
+        token_state.total_uses += 1;
+        state.next = (index + 1) % count;
+
+        Ok(TokenLease {
+            index,
+            label: self.tokens[index].label.clone(),
+            token: self.tokens[index].token.clone(),
+        })
+    }
+
+    /// Penalize the token if `err` says it is unusable, see the module docs. Returns how
+    /// the token was penalized, if at all.
+    ///
+    /// A pool of one token is never penalized, as there is nothing to fail over to.
+    pub(crate) fn report_error(&self, lease: &TokenLease, api_name: &str, err: &TushareError) -> Option<TokenPenalty> {
+        if self.tokens.len() < 2 {
+            return None;
+        }
+        let now = Instant::now();
+        let mut state = self.lock_state();
+        let token_state = &mut state.tokens[lease.index];
+        match err.kind() {
+            ErrorKind::InvalidToken | ErrorKind::QuotaExhausted => {
+                token_state.quarantined_until = Some(now + self.quarantine);
+                Some(TokenPenalty::Quarantined(self.quarantine))
+            }
+            ErrorKind::PermissionDenied => {
+                token_state.denied_until.insert(api_name.to_string(), now + self.quarantine);
+                Some(TokenPenalty::Denied(self.quarantine))
+            }
+            ErrorKind::RateLimited => {
+                // Rests until the oldest call of its window drops out, like a spent budget
+                prune(&mut token_state.recent, now);
+                let oldest = token_state.recent.front().copied().unwrap_or(now);
+                let until = oldest + BUDGET_WINDOW;
+                token_state.throttled_until = Some(until);
+                Some(TokenPenalty::Throttled(until.saturating_duration_since(now)))
+            }
+            _ => None,
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn prune(recent: &mut VecDeque<Instant>, now: Instant) {
+    while let Some(first) = recent.front() {
+        if now.saturating_duration_since(*first) >= BUDGET_WINDOW {
+            recent.pop_front();
+        } else {
+            break;
+        }
+    }
+}
+
+fn min_duration(current: Option<Duration>, candidate: Duration) -> Option<Duration> {
+    Some(current.map_or(candidate, |c| c.min(candidate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(selection: TokenSelection) -> TokenPool {
+        TokenPool::new(
+            vec![
+                PooledToken::new("a", "token-a").with_per_minute_budget(2),
+                PooledToken::new("b", "token-b"),
+            ],
+            selection,
+            Duration::from_secs(60),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_robin_respects_budgets() {
+        let pool = pool(TokenSelection::RoundRobin);
+        let labels: Vec<String> = (0..5).map(|_| pool.acquire("daily").unwrap().label).collect();
+        assert_eq!(labels, ["a", "b", "a", "b", "b"]);
+    }
+
+    fn api_error(code: i32, message: &str) -> TushareError {
+        TushareError::ApiError {
+            code,
+            message: message.to_string(),
+            request_id: "r1".to_string(),
+        }
+    }
+
+    #[test]
+    fn quarantined_token_is_skipped() {
+        let pool = pool(TokenSelection::LeastUsed);
+        let lease = pool.acquire("daily").unwrap();
+        assert_eq!(lease.label, "a");
+
+        let penalty = pool.report_error(&lease, "daily", &api_error(40101, "您的token不对，请确认。"));
+        assert!(matches!(penalty, Some(TokenPenalty::Quarantined(_))));
+        assert!((0..3).all(|_| pool.acquire("stock_basic").unwrap().label == "b"));
+        assert!(pool.stats()[0].quarantined_for.is_some());
+    }
+
+    #[test]
+    fn permission_errors_only_exclude_the_failing_api() {
+        let pool = pool(TokenSelection::RoundRobin);
+        let lease = pool.acquire("moneyflow").unwrap();
+        assert_eq!(lease.label, "a");
+
+        let penalty = pool.report_error(&lease, "moneyflow", &api_error(40203, "抱歉，您没有访问该接口的权限"));
+        assert!(matches!(penalty, Some(TokenPenalty::Denied(_))));
+        assert_eq!(pool.acquire("moneyflow").unwrap().label, "b");
+        assert_eq!(pool.acquire("moneyflow").unwrap().label, "b");
+        assert_eq!(pool.acquire("daily").unwrap().label, "a");
+        assert_eq!(pool.stats()[0].quarantined_for, None);
+        assert_eq!(pool.stats()[0].denied_apis, ["moneyflow"]);
+    }
+
+    #[test]
+    fn rate_limited_token_waits_for_its_window() {
+        let pool = pool(TokenSelection::RoundRobin);
+        let lease = pool.acquire("daily").unwrap();
+
+        let penalty = pool.report_error(&lease, "daily", &api_error(40203, "抱歉，您每分钟最多访问该接口500次"));
+        assert!(matches!(penalty, Some(TokenPenalty::Throttled(wait)) if wait <= BUDGET_WINDOW));
+        assert!((0..3).all(|_| pool.acquire("stock_basic").unwrap().label == "b"));
+        assert_eq!(pool.stats()[0].quarantined_for, None);
+    }
+
+    #[test]
+    fn debug_never_shows_token() {
+        let debug = format!("{:?}", pool(TokenSelection::RoundRobin));
+        assert!(!debug.contains("token-a"));
+        assert!(debug.contains("\"a\""));
+    }
+}
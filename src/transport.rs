@@ -0,0 +1,266 @@
+//! Pluggable HTTP transport for [`TushareClient`](crate::TushareClient)
+//!
+//! The client serializes each request (including the token) to a JSON body and hands
+//! it to a [`Transport`], which only has to deliver it to an endpoint and return the
+//! HTTP status and body. Everything above that — logging, endpoint failover, response
+//! parsing and error mapping — stays in the client, so it can be exercised offline by
+//! injecting a custom transport such as [`InMemoryTransport`].
+
+use crate::client::HttpClientConfig;
+use crate::error::TushareResult;
+use reqwest::Client;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Boxed, `Send` future returned by object-safe async traits in this crate
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A serialized request ready to be sent to one endpoint
+///
+/// `Debug` omits the body, since it carries the token.
+#[derive(Clone)]
+pub struct TransportRequest {
+    /// Endpoint URL the request is sent to
+    pub endpoint: String,
+    /// JSON request body, including the token
+    pub body: String,
+    /// Extra HTTP headers, e.g. added by a [`Middleware`](crate::middleware::Middleware)
+    pub headers: Vec<(String, String)>,
+    /// Per-request timeout overriding the transport's default
+    pub timeout: Option<Duration>,
+}
+
+impl TransportRequest {
+    /// Create a request without extra headers
+    pub fn new<E: Into<String>, B: Into<String>>(endpoint: E, body: B) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            body: body.into(),
+            headers: Vec::new(),
+            timeout: None,
+        }
+    }
+}
+
+impl fmt::Debug for TransportRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportRequest")
+            .field("endpoint", &self.endpoint)
+            .field("body_len", &self.body.len())
+            .field("headers", &self.headers)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Raw response returned by a [`Transport`]
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Response body
+    pub body: String,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+}
+
+impl TransportResponse {
+    /// Create a response with the given status and body
+    pub fn new<S: Into<String>>(status: u16, body: S) -> Self {
+        Self {
+            status,
+            body: body.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Add a response header
+    pub fn with_header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Value of the first header named `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Delay requested by a `Retry-After` header given in seconds
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.header("retry-after")?.trim().parse().ok().map(Duration::from_secs)
+    }
+
+    /// Create a `200 OK` response with the given body
+    pub fn ok<S: Into<String>>(body: S) -> Self {
+        Self::new(200, body)
+    }
+}
+
+/// Sends serialized requests to Tushare and returns status + body.
+///
+/// Implementations should return [`TushareError::HttpError`](crate::TushareError::HttpError) or
+/// [`TushareError::TimeoutError`](crate::TushareError::TimeoutError) for network-level failures, which makes the client
+/// fail over to the next configured endpoint.
+pub trait Transport: Send + Sync + fmt::Debug {
+    /// Send one request
+    fn send<'a>(&'a self, request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send<'a>(&'a self, request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>> {
+        (**self).send(request)
+    }
+}
+
+/// Default transport backed by `reqwest`
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Wrap an existing `reqwest::Client`
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Build a transport from an [`HttpClientConfig`]
+    pub fn from_config(config: &HttpClientConfig) -> TushareResult<Self> {
+        Ok(Self::new(config.build_client()?))
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send<'a>(&'a self, request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>> {
+        Box::pin(async move {
+            let mut builder = self
+                .client
+                .post(&request.endpoint)
+                .header(reqwest::header::CONTENT_TYPE, "application/json");
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+            if let Some(timeout) = request.timeout {
+                builder = builder.timeout(timeout);
+            }
+            let response = builder.body(request.body).send().await?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            let body = response.text().await?;
+            Ok(TransportResponse { status, body, headers })
+        })
+    }
+}
+
+type Handler = dyn Fn(&TransportRequest) -> TushareResult<TransportResponse> + Send + Sync;
+
+/// Transport that answers requests from a closure without touching the network.
+///
+/// Every request is recorded and can be inspected with [`Self::requests`].
+///
+/// # Example
+///
+/// ```rust
+/// use tushare_api::TushareClient;
+/// use tushare_api::transport::{InMemoryTransport, TransportResponse};
+///
+/// let transport = InMemoryTransport::new(|_request| {
+///     Ok(TransportResponse::ok(
+///         r#"{"request_id":"1","code":0,"msg":"","data":{"fields":["ts_code"],"items":[["000001.SZ"]],"has_more":false,"count":1}}"#,
+///     ))
+/// });
+///
+/// let client = TushareClient::builder()
+///     .with_token("test-token")
+///     .with_transport(transport)
+///     .build()?;
+/// # Ok::<(), tushare_api::TushareError>(())
+/// ```
+pub struct InMemoryTransport {
+    handler: Box<Handler>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl InMemoryTransport {
+    /// Create a transport that answers every request with `handler`
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&TransportRequest) -> TushareResult<TransportResponse> + Send + Sync + 'static,
+    {
+        Self {
+            handler: Box::new(handler),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Requests received so far, in order
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+impl fmt::Debug for InMemoryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InMemoryTransport")
+            .field("requests", &self.requests)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for InMemoryTransport {
+    fn send<'a>(&'a self, request: TransportRequest) -> BoxFuture<'a, TushareResult<TransportResponse>> {
+        let result = (self.handler)(&request);
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(request);
+        Box::pin(async move { result })
+    }
+}
+
+/// Canned Tushare replies shared by the crate's tests
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{InMemoryTransport, TransportResponse};
+    use serde_json::{json, Value};
+
+    /// `200 OK` reply carrying `rows` (JSON arrays) of `fields`
+    pub(crate) fn rows_response(fields: &[&str], rows: Vec<Value>) -> TransportResponse {
+        let count = rows.len();
+        paged_rows_response(fields, rows, false, count)
+    }
+
+    /// Like [`rows_response`], for one page of a longer result
+    pub(crate) fn paged_rows_response(fields: &[&str], rows: Vec<Value>, has_more: bool, count: usize) -> TransportResponse {
+        let body = json!({
+            "request_id": "r1", "code": 0, "msg": "",
+            "data": {"fields": fields, "items": rows, "has_more": has_more, "count": count}
+        });
+        TransportResponse::ok(body.to_string())
+    }
+
+    /// `200 OK` reply with a non-zero Tushare code
+    pub(crate) fn error_response(code: i32, msg: &str) -> TransportResponse {
+        let body = json!({"request_id": "r2", "code": code, "msg": msg, "data": null});
+        TransportResponse::ok(body.to_string())
+    }
+
+    /// Transport answering every request with `rows` of `fields`
+    pub(crate) fn rows_transport(fields: &'static [&'static str], rows: Vec<Value>) -> InMemoryTransport {
+        InMemoryTransport::new(move |_| Ok(rows_response(fields, rows.clone())))
+    }
+}
@@ -0,0 +1,193 @@
+//! Detection of silently truncated responses
+//!
+//! Many Tushare APIs return at most a fixed number of rows per call and may report
+//! `has_more == false` even when rows were cut off. The client knows the documented row cap of
+//! common APIs ([`Api::row_cap`](crate::Api::row_cap)); caps can be added or overridden with
+//! [`TushareClientBuilder::with_row_cap`](crate::client::TushareClientBuilder::with_row_cap). A
+//! response whose row count reaches the cap is handled according to the
+//! [`TruncationPolicy`]: logged as a warning (the default), rejected with
+//! [`TushareError::PossiblyTruncated`](crate::TushareError::PossiblyTruncated), or ignored.
+//!
+//! [`TushareClientEx::with_auto_split`](crate::TushareClientEx::with_auto_split) goes further
+//! and re-issues such a request in two halves, split by `ts_code` list or by date range,
+//! until no half is truncated.
+//!
+//! Paged requests (with `offset` or `limit`, e.g. from
+//! [`TushareClient::call_api_paginated`](crate::TushareClient::call_api_paginated)) are never
+//! checked or split: a full page is expected, and the next page fetches the remaining rows.
+//! Windows of a [chunked fetch](crate::chunking) are not auto-split either; the chunked fetch
+//! splits them itself.
+
+use crate::batching::TS_CODE;
+use crate::chunking::{END_DATE_PARAM, START_DATE_PARAM};
+use crate::dates;
+use crate::chunking;
+use crate::pagination;
+use crate::types::{TushareRequest, TushareResponse};
+
+/// What the client does with a response that reaches the API's row cap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    /// Return the response without checking
+    Ignore,
+    /// Return the response and log a warning
+    #[default]
+    Warn,
+    /// Fail with [`TushareError::PossiblyTruncated`](crate::TushareError::PossiblyTruncated)
+    Error,
+}
+
+/// Split a truncated request in two: by its `ts_code` list if it has several codes,
+/// otherwise by its date range. `None` if it covers a single code and a single day, or asks
+/// for a page (`offset`/`limit`): the same offset would select other rows of each half.
+pub(crate) fn split_request(request: &TushareRequest) -> Option<(TushareRequest, TushareRequest)> {
+    if pagination::is_paged(request) {
+        return None;
+    }
+    let with_param = |name: &str, value: String| {
+        let mut half = request.clone();
+        half.params.insert(name.to_string(), value);
+        half
+    };
+
+    if let Some(codes) = request.params.get(TS_CODE) {
+        let codes: Vec<&str> = codes.split(',').map(str::trim).filter(|code| !code.is_empty()).collect();
+        if codes.len() > 1 {
+            let (first, second) = codes.split_at(codes.len() / 2);
+            return Some((with_param(TS_CODE, first.join(",")), with_param(TS_CODE, second.join(","))));
+        }
+    }
+
+    let start = dates::parse_date(request.params.get(START_DATE_PARAM)?).ok()?;
+    let end = match request.params.get(END_DATE_PARAM) {
+        Some(end_date) => dates::parse_date(end_date).ok()?,
+        None => dates::parse_date(&dates::today()).ok()?,
+    };
+    if start >= end {
+        return None;
+    }
+    let middle = start + (end - start) / 2;
+    let first = with_param(END_DATE_PARAM, dates::format_date(middle));
+    let mut second = with_param(START_DATE_PARAM, dates::format_date(middle + 1));
+    second.params.insert(END_DATE_PARAM.to_string(), dates::format_date(end));
+    Some((first, second))
+}
+
+/// Concatenate the rows of two halves returned for a split request
+pub(crate) fn merge_halves(first: TushareResponse, second: TushareResponse) -> TushareResponse {
+    let data = match (first.data.clone(), second.data) {
+        (Some(a), Some(b)) => Some(chunking::merge([a, b].into_iter(), None)),
+        (a, b) => a.or(b),
+    };
+    TushareResponse { data, ..first }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Api;
+    use crate::transport::fixtures::{paged_rows_response, rows_response};
+    use crate::transport::InMemoryTransport;
+    use crate::{ErrorKind, TushareClient, TushareClientEx};
+    use crate::error::TushareResult;
+    use crate::traits::FromTushareData;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Code(String);
+
+    impl FromTushareData for Code {
+        fn from_row(fields: &[String], values: &[serde_json::Value]) -> TushareResult<Self> {
+            Ok(Code(crate::utils::get_string_field(fields, values, "ts_code")?))
+        }
+    }
+
+    #[test]
+    fn splits_by_codes_then_by_dates() {
+        let request = TushareRequest::new(
+            Api::Daily,
+            [("ts_code", "000001.SZ,000002.SZ,600000.SH"), ("start_date", "20240101"), ("end_date", "20240110")],
+            ["close"],
+        );
+        let (first, second) = split_request(&request).unwrap();
+        assert_eq!(first.params["ts_code"], "000001.SZ");
+        assert_eq!(second.params["ts_code"], "000002.SZ,600000.SH");
+
+        let (first, second) = split_request(&first).unwrap();
+        assert_eq!((first.params["start_date"].as_str(), first.params["end_date"].as_str()), ("20240101", "20240105"));
+        assert_eq!((second.params["start_date"].as_str(), second.params["end_date"].as_str()), ("20240106", "20240110"));
+
+        let single_day = TushareRequest::new(Api::Daily, [("trade_date", "20240102")], ["close"]);
+        assert!(split_request(&single_day).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_or_splits_responses_at_the_row_cap() {
+        // One row per requested code
+        let transport = Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let items: Vec<_> = body["params"]["ts_code"]
+                .as_str()
+                .unwrap()
+                .split(',')
+                .map(|code| json!([code]))
+                .collect();
+            Ok(rows_response(&["ts_code"], items))
+        }));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_row_cap(Api::Daily, 2)
+            .with_truncation_policy(TruncationPolicy::Error)
+            .build()
+            .unwrap();
+        let request = TushareRequest::new(Api::Daily, [("ts_code", "000001.SZ,000002.SZ"), ("trade_date", "20240102")], ["ts_code"]);
+
+        let err = client.call_api(&request).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PossiblyTruncated);
+
+        let client = TushareClientEx::new(client).with_auto_split(true);
+        let data = client.call_api(&request).await.unwrap().data.unwrap();
+        assert_eq!(data.items.len(), 2);
+        // The truncated call, then one call per code
+        assert_eq!(transport.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn leaves_paginated_requests_alone() {
+        // One row per requested code, paged by offset/limit
+        let transport = Arc::new(InMemoryTransport::new(|request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let params = &body["params"];
+            let codes: Vec<&str> = params["ts_code"].as_str().unwrap().split(',').collect();
+            let offset: usize = params["offset"].as_str().unwrap().parse().unwrap();
+            let limit: usize = params["limit"].as_str().unwrap().parse().unwrap();
+            let end = (offset + limit).min(codes.len());
+            let items: Vec<_> = codes[offset..end].iter().map(|code| json!([code])).collect();
+            Ok(paged_rows_response(&["ts_code"], items, end < codes.len(), codes.len()))
+        }));
+        let client = TushareClient::builder()
+            .with_token("test-token")
+            .with_transport(transport.clone())
+            .with_row_cap(Api::Daily, 2)
+            .with_truncation_policy(TruncationPolicy::Error)
+            .build()
+            .unwrap();
+        let client = TushareClientEx::new(client).with_auto_split(true);
+        let request = TushareRequest::new(
+            Api::Daily,
+            [("ts_code", "000001.SZ,000002.SZ,600000.SH"), ("trade_date", "20240102")],
+            ["ts_code"],
+        );
+
+        // Pages of the row cap are neither rejected nor split
+        let pages = crate::pagination::collect_all(client.call_api_paginated::<Code, _>(&request, 2))
+            .await
+            .unwrap();
+        let codes: Vec<&str> = pages.iter().map(|code| code.0.as_str()).collect();
+        assert_eq!(codes, ["000001.SZ", "000002.SZ", "600000.SH"]);
+        assert_eq!(transport.requests().len(), 2);
+        assert!(transport.requests().iter().all(|request| request.body.contains("000001.SZ,000002.SZ,600000.SH")));
+    }
+}
@@ -1,8 +1,7 @@
 use crate::api::{Api, serialize_api_name};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use crate::TushareError;
-use std::convert::Infallible;
+use std::collections::{BTreeMap, HashMap};
+use crate::{TushareError, TushareResult};
 
 /// Tushare API request structure
 ///
@@ -15,11 +14,9 @@ pub struct TushareRequest {
     pub fields: Vec<String>,
 }
 
-impl TryFrom<&TushareRequest> for TushareRequest {
-    type Error = Infallible;
-
-    fn try_from(value: &TushareRequest) -> Result<Self, Self::Error> {
-        Ok(value.clone())
+impl From<&TushareRequest> for TushareRequest {
+    fn from(value: &TushareRequest) -> Self {
+        value.clone()
     }
 }
 
@@ -92,6 +89,109 @@ impl TushareRequest {
         self.fields.push(field.into());
         self
     }
+
+    /// Check params and fields against the schema of the API, if it has one.
+    ///
+    /// Only a few core APIs have a schema (see [`crate::schema`]); requests to any other
+    /// API always pass.
+    pub fn validate(&self) -> TushareResult<()> {
+        crate::schema::validate(self)
+    }
+
+    /// Canonical form of this request, independent of param and field order
+    pub fn canonical_key(&self) -> RequestKey {
+        RequestKey::from(self)
+    }
+}
+
+/// Canonical, hashable form of a [`TushareRequest`]
+///
+/// Params are sorted by name and fields are sorted and deduplicated, so two requests
+/// that only differ in ordering map to the same key. Security codes in `ts_code` are
+/// trimmed and upper-cased, as Tushare matches them case-insensitively. The token is never part of a request,
+/// so keys are safe to persist (e.g. as fixture or cache file names).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RequestKey {
+    pub api_name: String,
+    pub params: BTreeMap<String, String>,
+    pub fields: Vec<String>,
+}
+
+impl RequestKey {
+    /// File name for storing data of this key on disk: `<api_name>-<fingerprint>.json`,
+    /// with characters unsafe in file names replaced by `_`
+    pub(crate) fn file_name(&self) -> String {
+        let api_name: String = self
+            .api_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect();
+        format!("{}-{}.json", api_name, self.fingerprint())
+    }
+
+    /// Stable 64-bit FNV-1a fingerprint of the key, as 16 hex digits
+    ///
+    /// Unlike `std::hash::Hash`, the value does not change between runs or Rust versions.
+    pub fn fingerprint(&self) -> String {
+        const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for byte in bytes.iter().chain(std::iter::once(&0u8)) {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        feed(self.api_name.as_bytes());
+        for (key, value) in &self.params {
+            feed(key.as_bytes());
+            feed(value.as_bytes());
+        }
+        for field in &self.fields {
+            feed(field.as_bytes());
+        }
+
+        format!("{hash:016x}")
+    }
+}
+
+impl From<&TushareRequest> for RequestKey {
+    fn from(request: &TushareRequest) -> Self {
+        let params = request
+            .params
+            .iter()
+            .map(|(k, v)| {
+                let k = k.trim();
+                let v = if k == "ts_code" { canonical_code(v) } else { v.trim().to_string() };
+                (k.to_string(), v)
+            })
+            .collect();
+        let mut fields: Vec<String> = request
+            .fields
+            .iter()
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+        fields.sort();
+        fields.dedup();
+
+        Self {
+            api_name: request.api_name.name(),
+            params,
+            fields,
+        }
+    }
+}
+
+/// Canonical form of a `ts_code` value: each comma-separated code trimmed and upper-cased
+pub(crate) fn canonical_code(codes: &str) -> String {
+    codes
+        .split(',')
+        .map(|code| code.trim().to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 /// Type alias retained for backward compatibility
@@ -238,12 +338,12 @@ impl<T> TushareEntityList<T> {
     }
 
     /// Get an iterator over the items
-    pub fn iter(&self) -> std::slice::Iter<T> {
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
         self.items.iter()
     }
 
     /// Get a mutable iterator over the items
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<T> {
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
         self.items.iter_mut()
     }
 
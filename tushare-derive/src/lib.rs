@@ -15,7 +15,7 @@ use syn::{parse_macro_input, DeriveInput, Data, Fields, Type};
 /// 
 /// # Example
 /// 
-/// ```rust
+/// ```rust,ignore
 /// use tushare_derive::FromTushareData;
 /// 
 /// #[derive(FromTushareData)]
@@ -55,39 +55,39 @@ pub fn derive_from_tushare_data(input: TokenStream) -> TokenStream {
         let mut date_format: Option<String> = None;
         
         for attr in &field.attrs {
-            if attr.path().is_ident("tushare") {
-                if let Ok(meta_list) = attr.meta.require_list() {
-                    let tokens_str = meta_list.tokens.to_string();
-                    
-                    // Parse field = "value" pattern
-                    if let Some(field_start) = tokens_str.find("field") {
-                        let after_field = &tokens_str[field_start + 5..]; // Skip "field"
-                        if let Some(eq_pos) = after_field.find('=') {
-                            let after_eq = &after_field[eq_pos + 1..].trim();
-                            if let Some(start_quote) = after_eq.find('"') {
-                                let after_start_quote = &after_eq[start_quote + 1..];
-                                if let Some(end_quote) = after_start_quote.find('"') {
-                                    api_field_name = after_start_quote[..end_quote].to_string();
-                                }
+            if attr.path().is_ident("tushare")
+                && let Ok(meta_list) = attr.meta.require_list()
+            {
+                let tokens_str = meta_list.tokens.to_string();
+                
+                // Parse field = "value" pattern
+                if let Some(field_start) = tokens_str.find("field") {
+                    let after_field = &tokens_str[field_start + 5..]; // Skip "field"
+                    if let Some(eq_pos) = after_field.find('=') {
+                        let after_eq = &after_field[eq_pos + 1..].trim();
+                        if let Some(start_quote) = after_eq.find('"') {
+                            let after_start_quote = &after_eq[start_quote + 1..];
+                            if let Some(end_quote) = after_start_quote.find('"') {
+                                api_field_name = after_start_quote[..end_quote].to_string();
                             }
                         }
                     }
-                    
-                    // Check for skip attribute
-                    if tokens_str.contains("skip") {
-                        skip_field = true;
-                    }
-                    
-                    // Parse date_format = "value" pattern
-                    if let Some(format_start) = tokens_str.find("date_format") {
-                        let after_format = &tokens_str[format_start + 11..]; // Skip "date_format"
-                        if let Some(eq_pos) = after_format.find('=') {
-                            let after_eq = &after_format[eq_pos + 1..].trim();
-                            if let Some(start_quote) = after_eq.find('"') {
-                                let after_start_quote = &after_eq[start_quote + 1..];
-                                if let Some(end_quote) = after_start_quote.find('"') {
-                                    date_format = Some(after_start_quote[..end_quote].to_string());
-                                }
+                }
+                
+                // Check for skip attribute
+                if tokens_str.contains("skip") {
+                    skip_field = true;
+                }
+                
+                // Parse date_format = "value" pattern
+                if let Some(format_start) = tokens_str.find("date_format") {
+                    let after_format = &tokens_str[format_start + 11..]; // Skip "date_format"
+                    if let Some(eq_pos) = after_format.find('=') {
+                        let after_eq = &after_format[eq_pos + 1..].trim();
+                        if let Some(start_quote) = after_eq.find('"') {
+                            let after_start_quote = &after_eq[start_quote + 1..];
+                            if let Some(end_quote) = after_start_quote.find('"') {
+                                date_format = Some(after_start_quote[..end_quote].to_string());
                             }
                         }
                     }
@@ -169,25 +169,22 @@ pub fn derive_from_tushare_data(input: TokenStream) -> TokenStream {
 
 // Helper functions for type checking
 fn is_option_type(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            return segment.ident == "Option";
-        }
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return segment.ident == "Option";
     }
     false
 }
 
 fn extract_option_inner_type(ty: &Type) -> Type {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            if segment.ident == "Option" {
-                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                        return inner_ty.clone();
-                    }
-                }
-            }
-        }
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return inner_ty.clone();
     }
     // Fallback to String if we can't extract the inner type
     syn::parse_str("String").unwrap()